@@ -7,10 +7,13 @@ JSON lines IPC contract:
 """
 
 from __future__ import annotations
+import gc
 import json
 import logging
 import logging.handlers
+import math
 import os
+import queue
 import sys
 import tempfile
 import threading
@@ -47,10 +50,61 @@ else:
 
 SAMPLE_RATE = 16_000
 CHANNELS = 1
+# Default model; `set_config` may pick another entry of MODEL_VARIANTS.
 MODEL_NAME = "v3_e2e_rnnt"
+# GigaAM variants as (decoder, end-to-end). CTC decodes fastest, RNNT is more accurate, and
+# end-to-end models also restore punctuation and letter case.
+MODEL_VARIANTS = {
+    "v3_ctc": ("ctc", False),
+    "v3_rnnt": ("rnnt", False),
+    "v3_e2e_ctc": ("ctc", True),
+    "v3_e2e_rnnt": ("rnnt", True),
+}
+# Where gigaam keeps downloaded checkpoints unless the package says otherwise.
+GIGAAM_CACHE_DIR = Path.home() / ".cache" / "gigaam"
 MAX_LOG_BYTES = 2 * 1024 * 1024
 MIN_RECORDING_SEC = 0.35
+# GigaAM short-form models handle ~25 s per call; longer audio is split into overlapping windows.
+CHUNK_SEC = 20.0
+CHUNK_OVERLAP_SEC = 1.0
+# Upper bound on words deduplicated where two windows overlap.
+MAX_OVERLAP_WORDS = 8
+# While recording, the buffered audio is re-decoded this often to produce partial transcripts.
+LIVE_PARTIAL_INTERVAL_SEC = 0.8
+LIVE_MIN_AUDIO_SEC = 1.0
+# Voiced audio a recording needs before VAD may auto-stop it or the model runs on it.
+VAD_MIN_SPEECH_MS = 200
+# `audio_level` events for the popup meter are sent at most this often while recording.
+AUDIO_LEVEL_INTERVAL_SEC = 0.05
+# Level blocks used to trim silence, and the margin kept around detected speech.
+TRIM_BLOCK_SAMPLES = 512
+TRIM_PADDING_MS = 250
+DEFAULT_VAD_THRESHOLD_DBFS = -45.0
+# With VAD off, only audio below this level counts as silence when trimming a recording, so the
+# user's speech threshold does not throw away quiet speech.
+SILENCE_FLOOR_DBFS = -60.0
 GIGAAM_GITHUB_REF = "https://github.com/salute-developers/GigaAM"
+SIDECAR_VERSION = "0.1.5"
+# Must match PROTOCOL_VERSION in src-tauri/src/protocol.rs.
+PROTOCOL_VERSION = 2
+SUPPORTED_COMMANDS = (
+    "init",
+    "start_recording",
+    "stop_and_transcribe",
+    "cancel_current",
+    "set_config",
+    "healthcheck",
+    "shutdown",
+    "preload_model",
+    "transcribe_file",
+    "list_input_devices",
+    "list_models",
+)
+SUPPORTED_AUDIO_SUFFIXES = (".wav", ".flac", ".ogg")
+# Languages the loaded model transcribes, reported in `ready`; GigaAM models are Russian only.
+SUPPORTED_LANGUAGES = ("ru",)
+# `language_mode` that leaves the choice to the sidecar: the first supported language.
+AUTO_LANGUAGE = "auto"
 
 
 @dataclass
@@ -58,6 +112,62 @@ class RuntimeConfig:
     language_mode: str = "ru"
     popup_timeout_sec: int = 10
     model_keepalive_min: int = 5
+    streaming_partials: bool = False
+    # Trailing silence that ends a recording; 0 disables voice activity detection.
+    vad_silence_ms: int = 0
+    vad_threshold_dbfs: float = DEFAULT_VAD_THRESHOLD_DBFS
+    # Name of the microphone to record from; empty means the system default.
+    input_device: str = ""
+    # Directory that keeps a FLAC copy of every recording; empty keeps no audio.
+    recordings_dir: str = ""
+    model: str = MODEL_NAME
+
+
+class EnergyVad:
+    """Energy-based voice activity detector fed with the level of each audio block.
+
+    Fires once speech was heard and the level then stayed below the threshold for
+    `silence_ms`; silence before the first word never stops the recording.
+    """
+
+    def __init__(self, threshold_dbfs: float, silence_ms: int) -> None:
+        self.threshold_dbfs = threshold_dbfs
+        self.silence_ms = silence_ms
+        self.speech_ms = 0.0
+        self.trailing_silence_ms = 0.0
+
+    def update(self, level_dbfs: float, block_ms: float) -> bool:
+        if level_dbfs >= self.threshold_dbfs:
+            self.speech_ms += block_ms
+            self.trailing_silence_ms = 0.0
+            return False
+        self.trailing_silence_ms += block_ms
+        return self.speech_ms >= VAD_MIN_SPEECH_MS and self.trailing_silence_ms >= self.silence_ms
+
+
+class LevelMeter:
+    """Accumulates audio blocks and yields (rms, peak) at most once per interval."""
+
+    def __init__(self, interval_sec: float = AUDIO_LEVEL_INTERVAL_SEC) -> None:
+        self.interval_sec = interval_sec
+        self.sum_squares = 0.0
+        self.samples = 0
+        self.peak = 0.0
+        self.last_report = 0.0
+
+    def add(self, sum_squares: float, peak: float, samples: int, now: float) -> tuple[float, float] | None:
+        self.sum_squares += sum_squares
+        self.samples += samples
+        self.peak = max(self.peak, peak)
+        if self.samples == 0 or now - self.last_report < self.interval_sec:
+            return None
+
+        level = (math.sqrt(self.sum_squares / self.samples), self.peak)
+        self.sum_squares = 0.0
+        self.samples = 0
+        self.peak = 0.0
+        self.last_report = now
+        return level
 
 
 @dataclass
@@ -69,12 +179,24 @@ class AppState:
     model_last_used_at: float = 0.0
     transcribing: bool = False
     model_lock: threading.Lock = field(default_factory=threading.Lock)
+    # Serializes model inference between the live decoder and the final transcription.
+    decode_lock: threading.Lock = field(default_factory=threading.Lock)
 
     audio_lock: threading.Lock = field(default_factory=threading.Lock)
     stream: sd.InputStream | None = None
     frames: list[np.ndarray] = field(default_factory=list)
     recording: bool = False
     recording_started_at: float = 0.0
+    recording_request_id: int | None = None
+    vad: EnergyVad | None = None
+    # Block levels from the audio callback, reported by `level_reporter` on its own thread.
+    levels: queue.SimpleQueue | None = None
+    level_thread: threading.Thread | None = None
+    # Set when the VAD ended the recording, so a late stop command does not stop it twice.
+    auto_stopped: bool = False
+
+    live_thread: threading.Thread | None = None
+    live_stop: threading.Event = field(default_factory=threading.Event)
 
     transcribe_thread: threading.Thread | None = None
     cancel_event: threading.Event = field(default_factory=threading.Event)
@@ -103,12 +225,21 @@ LOGGER = setup_logger()
 STATE = AppState()
 
 
+# Events are emitted from the stdin, worker, live decoder and level threads; one line at a time.
+EMIT_LOCK = threading.Lock()
+
+
 def emit(event: str, **payload: Any) -> None:
     data = {"event": event, **payload}
+    # `id` echoes the command this event answers; unsolicited events carry none.
+    if data.get("id") is None:
+        data.pop("id", None)
+    # Keep IPC payload ASCII-safe to avoid locale-specific stdout encodings on Windows pipes.
+    line = json.dumps(data, ensure_ascii=True) + "\n"
     try:
-        # Keep IPC payload ASCII-safe to avoid locale-specific stdout encodings on Windows pipes.
-        sys.stdout.write(json.dumps(data, ensure_ascii=True) + "\n")
-        sys.stdout.flush()
+        with EMIT_LOCK:
+            sys.stdout.write(line)
+            sys.stdout.flush()
     except Exception as exc:  # pragma: no cover - io failure
         LOGGER.error("failed to emit event: %s", exc)
 
@@ -119,59 +250,94 @@ def choose_device() -> str:
     return "cuda" if torch.cuda.is_available() else "cpu"
 
 
-def load_model_if_needed() -> None:
+def load_model_if_needed(request_id: int | None = None) -> None:
+    """Load the configured model, replacing a loaded one if the setting changed since."""
     with STATE.model_lock:
-        if STATE.model is not None:
+        if STATE.model is not None and STATE.model_name_used == STATE.config.model:
             STATE.model_last_used_at = time.monotonic()
             return
 
+    # Decoding reads STATE.model under decode_lock only, so a swap must hold it too. Take it
+    # before model_lock, in the order the CUDA fallback in `transcribe_path_locked` uses.
+    with STATE.decode_lock, STATE.model_lock:
+        if STATE.model is not None:
+            if STATE.model_name_used == STATE.config.model:
+                STATE.model_last_used_at = time.monotonic()
+                return
+            LOGGER.info("switching model from '%s' to '%s'", STATE.model_name_used, STATE.config.model)
+            release_model_locked()
+
         if gigaam is None:
             raise RuntimeError(f"gigaam import failed: {GIGAAM_IMPORT_ERROR}")
 
-        preferred = choose_device()
-        LOGGER.info("loading model '%s' on %s", MODEL_NAME, preferred)
+        emit("model_loading", id=request_id, model=STATE.config.model)
+        started_at = time.perf_counter()
+        load_model_locked()
+        emit(
+            "model_loaded",
+            id=request_id,
+            model=STATE.model_name_used,
+            device=STATE.model_device,
+            load_ms=int((time.perf_counter() - started_at) * 1000),
+        )
 
-        try:
-            STATE.model = gigaam.load_model(
-                MODEL_NAME,
-                fp16_encoder=(preferred == "cuda"),
-                use_flash=False,
-                device=preferred,
-            )
-            STATE.model_device = preferred
-            STATE.model_name_used = MODEL_NAME
-            STATE.model_last_used_at = time.monotonic()
-            LOGGER.info("loaded model '%s' on %s", MODEL_NAME, preferred)
-            return
-        except ValueError as exc:
-            message = str(exc)
-            if "Model 'v3_e2e_rnnt' not found" in message:
-                raise RuntimeError(
-                    "Installed gigaam package has no v3_e2e_rnnt. "
-                    f"Rebuild sidecar with gigaam from {GIGAAM_GITHUB_REF}"
-                ) from exc
-            if preferred != "cuda":
-                raise
-            LOGGER.warning("failed to load model '%s' on cuda: %s", MODEL_NAME, exc)
-        except Exception as exc:
-            if preferred != "cuda":
-                raise
-            LOGGER.warning("failed to load model '%s' on cuda: %s", MODEL_NAME, exc)
 
-        LOGGER.warning("trying CPU fallback for model '%s'", MODEL_NAME)
-        try:
-            STATE.model = gigaam.load_model(
-                MODEL_NAME,
-                fp16_encoder=False,
-                use_flash=False,
-                device="cpu",
-            )
-            STATE.model_device = "cpu"
-            STATE.model_name_used = MODEL_NAME
-            STATE.model_last_used_at = time.monotonic()
-            LOGGER.warning("loaded model '%s' with CPU fallback", MODEL_NAME)
-        except Exception as exc:
-            raise RuntimeError(f"Unable to load ASR model '{MODEL_NAME}': {exc}") from exc
+def release_model_locked() -> None:
+    """Drop the loaded model and return its memory; caller holds decode_lock and model_lock."""
+    STATE.model = None
+    gc.collect()
+    if torch.cuda.is_available():
+        torch.cuda.empty_cache()
+
+
+def load_gigaam(model_name: str, device: str) -> Any:
+    # An explicit download root keeps checkpoints where `checkpoint_path` looks for them.
+    return gigaam.load_model(
+        model_name,
+        fp16_encoder=(device == "cuda"),
+        use_flash=False,
+        device=device,
+        download_root=str(model_cache_dir()),
+    )
+
+
+def load_model_locked() -> None:
+    """Load the model with CUDA-to-CPU fallback; caller holds STATE.model_lock."""
+    preferred = choose_device()
+    model_name = STATE.config.model
+    LOGGER.info("loading model '%s' on %s", model_name, preferred)
+
+    try:
+        STATE.model = load_gigaam(model_name, preferred)
+        STATE.model_device = preferred
+        STATE.model_name_used = model_name
+        STATE.model_last_used_at = time.monotonic()
+        LOGGER.info("loaded model '%s' on %s", model_name, preferred)
+        return
+    except ValueError as exc:
+        message = str(exc)
+        if f"Model '{model_name}' not found" in message:
+            raise RuntimeError(
+                f"Installed gigaam package has no {model_name}. "
+                f"Rebuild sidecar with gigaam from {GIGAAM_GITHUB_REF}"
+            ) from exc
+        if preferred != "cuda":
+            raise
+        LOGGER.warning("failed to load model '%s' on cuda: %s", model_name, exc)
+    except Exception as exc:
+        if preferred != "cuda":
+            raise
+        LOGGER.warning("failed to load model '%s' on cuda: %s", model_name, exc)
+
+    LOGGER.warning("trying CPU fallback for model '%s'", model_name)
+    try:
+        STATE.model = load_gigaam(model_name, "cpu")
+        STATE.model_device = "cpu"
+        STATE.model_name_used = model_name
+        STATE.model_last_used_at = time.monotonic()
+        LOGGER.warning("loaded model '%s' with CPU fallback", model_name)
+    except Exception as exc:
+        raise RuntimeError(f"Unable to load ASR model '{model_name}': {exc}") from exc
 
 
 def touch_model_last_used() -> None:
@@ -218,15 +384,82 @@ def audio_callback(indata: np.ndarray, _frames: int, _time_info: Any, status: sd
     if status:
         LOGGER.warning("audio callback status: %s", status)
 
+    sum_squares = float(np.sum(np.square(indata, dtype=np.float64)))
+    peak = float(np.max(np.abs(indata))) if len(indata) else 0.0
+    rms = math.sqrt(sum_squares / len(indata)) if len(indata) else 0.0
+
     with STATE.audio_lock:
         if not STATE.recording:
             return
         STATE.frames.append(indata.copy())
+        if STATE.levels is not None:
+            STATE.levels.put((sum_squares, peak, len(indata)))
+        vad = STATE.vad
+        stop = (
+            vad is not None
+            and not STATE.auto_stopped
+            and vad.update(rms_to_dbfs(rms), len(indata) * 1000 / SAMPLE_RATE)
+        )
+        if stop:
+            STATE.auto_stopped = True
+        request_id = STATE.recording_request_id
+
+    if not stop:
+        return
+
+    # Stopping the stream from its own callback would deadlock.
+    threading.Thread(target=auto_stop, args=(request_id, vad.silence_ms), daemon=True).start()
+
+
+def level_reporter(levels: queue.SimpleQueue) -> None:
+    """Turn queued block levels into throttled `audio_level` events until `None` arrives.
+
+    Runs on its own thread so the realtime audio callback never waits on stdout.
+    """
+    meter = LevelMeter()
+    while (block := levels.get()) is not None:
+        level = meter.add(*block, time.monotonic())
+        if level is not None:
+            emit("audio_level", rms=round(level[0], 4), peak=round(level[1], 4))
+
+
+def start_level_reporter() -> None:
+    levels: queue.SimpleQueue = queue.SimpleQueue()
+    thread = threading.Thread(target=level_reporter, args=(levels,), daemon=True)
+    with STATE.audio_lock:
+        STATE.levels = levels
+        STATE.level_thread = thread
+    thread.start()
+
+
+def stop_level_reporter() -> None:
+    with STATE.audio_lock:
+        levels, STATE.levels = STATE.levels, None
+        thread, STATE.level_thread = STATE.level_thread, None
+    if levels is not None:
+        levels.put(None)
+    # Let the last levels out before `recording_stopped`.
+    if thread is not None and thread is not threading.current_thread():
+        thread.join(timeout=1.0)
+
+
+def rms_to_dbfs(rms: float) -> float:
+    return 20.0 * math.log10(max(rms, 1e-10))
+
+
+def level_dbfs(block: np.ndarray) -> float:
+    return rms_to_dbfs(float(np.sqrt(np.mean(np.square(block, dtype=np.float64)))))
+
 
+def auto_stop(request_id: int | None, silence_ms: int) -> None:
+    LOGGER.info("auto-stopping recording after %s ms of silence", silence_ms)
+    emit("auto_stopped", id=request_id, silence_ms=silence_ms)
+    finish_recording(request_id)
 
-def start_recording() -> None:
+
+def start_recording(request_id: int | None = None) -> None:
     if sd is None:
-        emit("error", message=f"Audio capture dependency missing: {SOUNDDEVICE_IMPORT_ERROR}")
+        emit("error", id=request_id, message=f"Audio capture dependency missing: {SOUNDDEVICE_IMPORT_ERROR}")
         return
 
     if STATE.recording:
@@ -238,33 +471,142 @@ def start_recording() -> None:
         STATE.frames = []
         STATE.recording = True
         STATE.recording_started_at = time.monotonic()
+        STATE.recording_request_id = request_id
+        STATE.auto_stopped = False
+        STATE.vad = (
+            EnergyVad(STATE.config.vad_threshold_dbfs, STATE.config.vad_silence_ms)
+            if STATE.config.vad_silence_ms > 0
+            else None
+        )
 
+    start_level_reporter()
+    device, fallback_reason = resolve_input_device()
     try:
-        stream = sd.InputStream(
-            samplerate=SAMPLE_RATE,
-            channels=CHANNELS,
-            dtype="float32",
-            callback=audio_callback,
-            blocksize=512,
-        )
-        stream.start()
+        try:
+            stream = open_input_stream(device)
+        except Exception as exc:
+            if device is None:
+                raise
+            LOGGER.exception("failed to open input device %s", device)
+            device, fallback_reason = None, f"it could not be opened ({exc})"
+            stream = open_input_stream(None)
         STATE.stream = stream
-        emit("recording_started")
-        LOGGER.info("recording started")
+        emit("recording_started", id=request_id)
+        LOGGER.info("recording started on device %s", "default" if device is None else device)
+        if fallback_reason:
+            requested = STATE.config.input_device
+            emit(
+                "input_device_fallback",
+                id=request_id,
+                requested=requested,
+                message=f"Microphone '{requested}' is unavailable: {fallback_reason}. Using the default input device.",
+            )
+        start_live_decoder(request_id)
     except Exception as exc:
         with STATE.audio_lock:
             STATE.recording = False
             STATE.frames = []
-        emit("error", message=f"Microphone error: {exc}")
+        stop_level_reporter()
+        emit("error", id=request_id, message=f"Microphone error: {exc}")
         LOGGER.exception("failed to start recording")
 
 
+def open_input_stream(device: int | None) -> sd.InputStream:
+    stream = sd.InputStream(
+        device=device,
+        samplerate=SAMPLE_RATE,
+        channels=CHANNELS,
+        dtype="float32",
+        callback=audio_callback,
+        blocksize=512,
+    )
+    try:
+        stream.start()
+    except Exception:
+        stream.close()
+        raise
+    return stream
+
+
+def refresh_device_list() -> None:
+    """PortAudio caches devices at startup; reinitialize it so plugged-in headsets show up."""
+    if STATE.stream is not None:
+        return
+    try:
+        sd._terminate()
+        sd._initialize()
+    except Exception:
+        LOGGER.exception("failed to refresh audio devices")
+
+
+def query_input_devices() -> tuple[list[dict[str, Any]], int | None]:
+    default_id = sd.default.device[0]
+    devices = [
+        {
+            "id": index,
+            "name": info["name"],
+            "sample_rate": int(info["default_samplerate"]),
+            "channels": int(info["max_input_channels"]),
+            "is_default": index == default_id,
+        }
+        for index, info in enumerate(sd.query_devices())
+        if info["max_input_channels"] > 0
+    ]
+    if not any(device["is_default"] for device in devices):
+        default_id = None
+    return devices, default_id
+
+
+def pick_input_device(devices: list[dict[str, Any]], wanted: str) -> int | None:
+    """Index of the input device named `wanted`, or None if it is not connected."""
+    for device in devices:
+        if device["name"] == wanted:
+            return device["id"]
+    return None
+
+
+def resolve_input_device() -> tuple[int | None, str | None]:
+    """Device to record from, plus why the configured one was not used (None if it was)."""
+    wanted = STATE.config.input_device
+    if not wanted:
+        return None, None
+    # Reinitializing PortAudio is not free, so only do it when the cached list misses the device.
+    for refresh in (False, True):
+        if refresh:
+            refresh_device_list()
+        try:
+            devices, _ = query_input_devices()
+        except Exception as exc:
+            return None, f"audio devices could not be listed ({exc})"
+        device = pick_input_device(devices, wanted)
+        if device is not None:
+            return device, None
+    return None, "it is not connected"
+
+
+def list_input_devices(request_id: int | None = None) -> None:
+    if sd is None:
+        emit("error", id=request_id, message=f"Audio capture dependency missing: {SOUNDDEVICE_IMPORT_ERROR}")
+        return
+
+    refresh_device_list()
+    try:
+        devices, default_id = query_input_devices()
+    except Exception as exc:
+        emit("error", id=request_id, message=f"Failed to list input devices: {exc}")
+        LOGGER.exception("failed to list input devices")
+        return
+    emit("input_devices", id=request_id, devices=devices, default_id=default_id)
+
+
 def stop_stream_if_needed() -> list[np.ndarray]:
     with STATE.audio_lock:
         frames = STATE.frames[:]
         STATE.frames = []
         STATE.recording = False
 
+    stop_level_reporter()
+    stop_live_decoder()
     stream = STATE.stream
     STATE.stream = None
     if stream is not None:
@@ -277,7 +619,15 @@ def stop_stream_if_needed() -> list[np.ndarray]:
     return frames
 
 
-def stop_and_transcribe() -> None:
+def stop_and_transcribe(request_id: int | None = None) -> None:
+    with STATE.audio_lock:
+        if STATE.auto_stopped:
+            LOGGER.info("ignoring stop: recording was already auto-stopped")
+            return
+    finish_recording(request_id)
+
+
+def finish_recording(request_id: int | None) -> None:
     with STATE.audio_lock:
         started_at = STATE.recording_started_at
 
@@ -286,109 +636,329 @@ def stop_and_transcribe() -> None:
         time.sleep(MIN_RECORDING_SEC - elapsed)
 
     frames = stop_stream_if_needed()
-    emit("recording_stopped")
+    emit("recording_stopped", id=request_id)
 
     if not frames:
-        emit("error", message="No audio captured. Check microphone permission or hold hotkey longer.")
+        emit(
+            "error",
+            id=request_id,
+            message="No audio captured. Check microphone permission or hold hotkey longer.",
+        )
         return
 
     STATE.cancel_event = threading.Event()
 
-    thread = threading.Thread(target=transcribe_worker, args=(frames, STATE.cancel_event), daemon=True)
+    thread = threading.Thread(
+        target=transcribe_worker,
+        args=(frames, STATE.cancel_event, request_id),
+        daemon=True,
+    )
     STATE.transcribe_thread = thread
     thread.start()
 
 
-def send_streaming_partials(text: str, cancel_event: threading.Event) -> None:
-    words = text.split()
-    if not words:
+def start_live_decoder(request_id: int | None) -> None:
+    if not STATE.config.streaming_partials:
         return
 
-    partial = []
-    for word in words:
-        if cancel_event.is_set():
+    STATE.live_stop = threading.Event()
+    thread = threading.Thread(
+        target=live_decoder_worker,
+        args=(STATE.live_stop, request_id),
+        daemon=True,
+    )
+    STATE.live_thread = thread
+    thread.start()
+
+
+def stop_live_decoder() -> None:
+    STATE.live_stop.set()
+    thread = STATE.live_thread
+    STATE.live_thread = None
+    # Let an in-flight decode finish so it does not compete with the final transcription.
+    if thread is not None and thread.is_alive() and thread is not threading.current_thread():
+        thread.join(timeout=5.0)
+
+
+def decode_samples(samples: np.ndarray, temp_path: Path) -> str:
+    sf.write(temp_path, samples, SAMPLE_RATE)
+    return transcribe_path(temp_path)
+
+
+def live_decoder_worker(stop_event: threading.Event, request_id: int | None) -> None:
+    """Re-decode the growing recording and emit `partial_transcript` until recording stops.
+
+    Windows that can no longer change are decoded once and kept; only the trailing window is
+    decoded again on every pass.
+    """
+    temp_path: Path | None = None
+    committed_words: list[str] = []
+    previous_words: list[str] = []
+    committed_chunks = 0
+    last_text = ""
+    try:
+        if sf is None:
             return
-        partial.append(word)
-        emit("partial_transcript", text=" ".join(partial))
-        time.sleep(0.03)
+        load_model_if_needed(request_id)
+        with tempfile.NamedTemporaryFile(suffix=".wav", delete=False) as tmp:
+            temp_path = Path(tmp.name)
 
+        while not stop_event.wait(LIVE_PARTIAL_INTERVAL_SEC):
+            with STATE.audio_lock:
+                if not STATE.recording:
+                    return
+                frames = STATE.frames[:]
+            if not frames:
+                continue
+
+            audio = np.concatenate(frames, axis=0)
+            if audio.ndim > 1:
+                audio = audio[:, 0]
+            if len(audio) < LIVE_MIN_AUDIO_SEC * SAMPLE_RATE:
+                continue
+
+            chunks = plan_chunks(len(audio))
+            for start, end in chunks[committed_chunks:-1]:
+                chunk_words = decode_samples(audio[start:end], temp_path).split()
+                committed_words.extend(strip_overlap(previous_words, chunk_words))
+                previous_words = chunk_words
+                committed_chunks += 1
+
+            start, end = chunks[-1]
+            tail_words = strip_overlap(previous_words, decode_samples(audio[start:end], temp_path).split())
+            text = " ".join(committed_words + tail_words)
+            if text and text != last_text and not stop_event.is_set():
+                emit("partial_transcript", id=request_id, text=text)
+                last_text = text
+    except Exception:
+        # Partials are best effort; the final transcription reports real failures.
+        LOGGER.exception("live decoding failed")
+    finally:
+        if temp_path is not None and temp_path.exists():
+            try:
+                temp_path.unlink()
+            except OSError:
+                LOGGER.exception("failed to delete temp audio file")
 
-def transcribe_worker(frames: list[np.ndarray], cancel_event: threading.Event) -> None:
+
+def transcribe_worker(
+    frames: list[np.ndarray],
+    cancel_event: threading.Event,
+    request_id: int | None = None,
+) -> None:
     started_at = time.perf_counter()
-    temp_path: Path | None = None
     set_transcribing(True)
 
     try:
         audio = np.concatenate(frames, axis=0)
         if audio.ndim > 1:
             audio = audio[:, 0]
+        speech = trim_silence(audio, speech_threshold_dbfs(STATE.config))
+        if speech is None:
+            LOGGER.info("no speech detected; skipping transcription")
+            emit("no_speech", id=request_id)
+            return
+        save_recording(audio, request_id)
+        transcribe_audio(speech, cancel_event, request_id, started_at)
+    except Exception as exc:
+        emit("error", id=request_id, message=f"Transcription failed: {exc}")
+        LOGGER.exception("transcription failed")
+    finally:
+        set_transcribing(False)
 
-        if sf is None:
-            raise RuntimeError(f"Audio file dependency missing: {SOUNDFILE_IMPORT_ERROR}")
 
-        with tempfile.NamedTemporaryFile(suffix=".wav", delete=False) as tmp:
-            temp_path = Path(tmp.name)
+def recording_path(directory: Path, request_id: int | None, now: float) -> Path:
+    stamp = time.strftime("%Y%m%d-%H%M%S", time.localtime(now))
+    return directory / f"{stamp}-{int(now * 1000) % 1000:03d}-{request_id or 0}.flac"
 
-        sf.write(temp_path, audio, SAMPLE_RATE)
 
-        if cancel_event.is_set():
-            emit("job_cancelled")
-            return
+def save_recording(audio: np.ndarray, request_id: int | None) -> None:
+    """Keep the untrimmed recording when `recordings_dir` is configured; failures only log."""
+    if not STATE.config.recordings_dir or sf is None:
+        return
 
-        load_model_if_needed()
-        touch_model_last_used()
+    path = recording_path(Path(STATE.config.recordings_dir), request_id, time.time())
+    try:
+        path.parent.mkdir(parents=True, exist_ok=True)
+        sf.write(path, audio, SAMPLE_RATE)
+    except Exception:
+        LOGGER.exception("failed to save recording")
+        return
+    emit("recording_saved", id=request_id, path=str(path), duration_ms=len(audio) * 1000 // SAMPLE_RATE)
+
+
+def speech_bounds(
+    levels: list[float],
+    threshold_dbfs: float,
+    block_ms: float,
+    min_speech_ms: float = VAD_MIN_SPEECH_MS,
+) -> tuple[int, int] | None:
+    """First and one-past-last voiced block, or None when too little of the audio is speech."""
+    voiced = [i for i, level in enumerate(levels) if level >= threshold_dbfs]
+    if len(voiced) * block_ms < min_speech_ms:
+        return None
+    return voiced[0], voiced[-1] + 1
+
+
+def speech_threshold_dbfs(config: RuntimeConfig) -> float:
+    """The user's speech threshold while VAD is on, otherwise the fixed silence floor."""
+    return config.vad_threshold_dbfs if config.vad_silence_ms > 0 else SILENCE_FLOOR_DBFS
+
+
+def trim_silence(audio: np.ndarray, threshold_dbfs: float) -> np.ndarray | None:
+    """Cut leading and trailing silence, keeping a short margin; None if nobody spoke."""
+    levels = [
+        level_dbfs(audio[start : start + TRIM_BLOCK_SAMPLES])
+        for start in range(0, len(audio), TRIM_BLOCK_SAMPLES)
+    ]
+    bounds = speech_bounds(levels, threshold_dbfs, TRIM_BLOCK_SAMPLES * 1000 / SAMPLE_RATE)
+    if bounds is None:
+        return None
+
+    padding = TRIM_PADDING_MS * SAMPLE_RATE // 1000
+    start = max(bounds[0] * TRIM_BLOCK_SAMPLES - padding, 0)
+    end = min(bounds[1] * TRIM_BLOCK_SAMPLES + padding, len(audio))
+    return audio[start:end]
+
+
+def plan_chunks(
+    total_samples: int,
+    sample_rate: int = SAMPLE_RATE,
+    chunk_sec: float = CHUNK_SEC,
+    overlap_sec: float = CHUNK_OVERLAP_SEC,
+) -> list[tuple[int, int]]:
+    """Split `total_samples` into [start, end) windows of `chunk_sec` overlapping by `overlap_sec`."""
+    chunk = int(chunk_sec * sample_rate)
+    step = chunk - int(overlap_sec * sample_rate)
+    if total_samples <= chunk:
+        return [(0, total_samples)]
+
+    chunks = []
+    start = 0
+    while True:
+        end = min(start + chunk, total_samples)
+        chunks.append((start, end))
+        if end >= total_samples:
+            return chunks
+        start += step
+
+
+def normalize_word(word: str) -> str:
+    return word.strip(".,!?;:\"'()«»").lower()
+
+
+def strip_overlap(previous_words: list[str], words: list[str]) -> list[str]:
+    """Drop the leading words of `words` that repeat the tail of the previous window."""
+    limit = min(len(previous_words), len(words), MAX_OVERLAP_WORDS)
+    for n in range(limit, 0, -1):
+        tail = [normalize_word(w) for w in previous_words[-n:]]
+        head = [normalize_word(w) for w in words[:n]]
+        if tail == head:
+            return words[n:]
+    return words
+
+
+def transcribe_path(path: Path) -> str:
+    with STATE.decode_lock:
+        return transcribe_path_locked(path)
+
+
+def transcribe_path_locked(path: Path) -> str:
+    try:
+        result = STATE.model.transcribe(str(path))
+    except RuntimeError as exc:
+        text = str(exc).lower()
+        if "cuda" in text and STATE.model_device == "cuda":
+            LOGGER.warning("cuda runtime failed, fallback to cpu once: %s", exc)
+            with STATE.model_lock:
+                STATE.model = load_gigaam(STATE.model_name_used, "cpu")
+                STATE.model_device = "cpu"
+                STATE.model_last_used_at = time.monotonic()
+            result = STATE.model.transcribe(str(path))
+        else:
+            raise
 
-        try:
-            result = STATE.model.transcribe(str(temp_path))
-        except RuntimeError as exc:
-            text = str(exc).lower()
-            if "cuda" in text and STATE.model_device == "cuda":
-                LOGGER.warning("cuda runtime failed, fallback to cpu once: %s", exc)
-                with STATE.model_lock:
-                    STATE.model = gigaam.load_model(
-                        MODEL_NAME,
-                        fp16_encoder=False,
-                        use_flash=False,
-                        device="cpu",
-                    )
-                    STATE.model_device = "cpu"
-                    STATE.model_last_used_at = time.monotonic()
-                result = STATE.model.transcribe(str(temp_path))
-            else:
-                raise
+    if isinstance(result, dict):
+        return str(result.get("transcription", "")).strip()
+    return str(result).strip()
 
-        touch_model_last_used()
+
+def transcribe_audio(
+    audio: np.ndarray,
+    cancel_event: threading.Event,
+    request_id: int | None,
+    started_at: float,
+) -> None:
+    """Transcribe 16 kHz mono samples window by window.
+
+    Every window is reported as a `transcript_segment` with start/end timestamps. Words repeated
+    in the overlap with the previous window are dropped, so segments never overlap in time or text.
+    """
+    temp_path: Path | None = None
+    try:
+        if sf is None:
+            raise RuntimeError(f"Audio file dependency missing: {SOUNDFILE_IMPORT_ERROR}")
+
+        with tempfile.NamedTemporaryFile(suffix=".wav", delete=False) as tmp:
+            temp_path = Path(tmp.name)
 
         if cancel_event.is_set():
-            emit("job_cancelled")
+            emit("job_cancelled", id=request_id)
             return
 
-        if isinstance(result, dict):
-            text = str(result.get("transcription", "")).strip()
-        else:
-            text = str(result).strip()
+        load_model_if_needed(request_id)
+        touch_model_last_used()
 
-        send_streaming_partials(text, cancel_event)
+        chunks = plan_chunks(len(audio))
+        words: list[str] = []
+        previous_words: list[str] = []
+        previous_end_ms = 0
+        for index, (start, end) in enumerate(chunks):
+            if cancel_event.is_set():
+                emit("job_cancelled", id=request_id)
+                return
+
+            sf.write(temp_path, audio[start:end], SAMPLE_RATE)
+            chunk_words = transcribe_path(temp_path).split()
+            touch_model_last_used()
+
+            new_words = strip_overlap(previous_words, chunk_words)
+            end_ms = end * 1000 // SAMPLE_RATE
+            emit(
+                "transcript_segment",
+                id=request_id,
+                index=index,
+                start_ms=max(start * 1000 // SAMPLE_RATE, previous_end_ms),
+                end_ms=end_ms,
+                text=" ".join(new_words),
+            )
+            if len(chunks) > 1:
+                emit(
+                    "transcription_progress",
+                    id=request_id,
+                    stage="transcribing",
+                    progress=(index + 1) / len(chunks),
+                )
+            words.extend(new_words)
+            previous_words = chunk_words
+            previous_end_ms = end_ms
 
         if cancel_event.is_set():
-            emit("job_cancelled")
+            emit("job_cancelled", id=request_id)
             return
 
-        emit("final_transcript", text=text)
+        emit("final_transcript", id=request_id, text=" ".join(words))
 
         latency_ms = int((time.perf_counter() - started_at) * 1000)
         emit(
             "metrics",
+            id=request_id,
             latency_ms=latency_ms,
             device=STATE.model_device,
             model=STATE.model_name_used,
         )
         LOGGER.info("transcription done in %sms", latency_ms)
-    except Exception as exc:
-        emit("error", message=f"Transcription failed: {exc}")
-        LOGGER.exception("transcription failed")
     finally:
-        set_transcribing(False)
         if temp_path is not None and temp_path.exists():
             try:
                 temp_path.unlink()
@@ -396,7 +966,87 @@ def transcribe_worker(frames: list[np.ndarray], cancel_event: threading.Event) -
                 LOGGER.exception("failed to delete temp audio file")
 
 
-def cancel_current(silent: bool = False) -> None:
+def to_mono_16k(audio: np.ndarray, sample_rate: int) -> np.ndarray:
+    """Downmix to mono and resample to SAMPLE_RATE with linear interpolation."""
+    if audio.ndim > 1:
+        audio = audio.mean(axis=1)
+    audio = audio.astype(np.float32)
+    if sample_rate == SAMPLE_RATE or audio.size == 0:
+        return audio
+
+    target_len = int(round(audio.size * SAMPLE_RATE / sample_rate))
+    source_times = np.arange(audio.size) / sample_rate
+    target_times = np.arange(target_len) / SAMPLE_RATE
+    return np.interp(target_times, source_times, audio).astype(np.float32)
+
+
+def transcribe_file(path: Any, request_id: int | None = None) -> None:
+    if not isinstance(path, str) or not path:
+        emit("error", id=request_id, message="transcribe_file requires a file path")
+        return
+
+    file_path = Path(path)
+    if file_path.suffix.lower() not in SUPPORTED_AUDIO_SUFFIXES:
+        emit(
+            "error",
+            id=request_id,
+            message=f"Unsupported audio format '{file_path.suffix}'. Use WAV, FLAC or OGG.",
+        )
+        return
+
+    if not file_path.is_file():
+        emit("error", id=request_id, message=f"Audio file not found: {file_path}")
+        return
+
+    if sf is None:
+        emit("error", id=request_id, message=f"Audio file dependency missing: {SOUNDFILE_IMPORT_ERROR}")
+        return
+
+    cancel_current(silent=True)
+    STATE.cancel_event = threading.Event()
+
+    thread = threading.Thread(
+        target=file_transcribe_worker,
+        args=(file_path, STATE.cancel_event, request_id),
+        daemon=True,
+    )
+    STATE.transcribe_thread = thread
+    thread.start()
+
+
+def file_transcribe_worker(
+    file_path: Path,
+    cancel_event: threading.Event,
+    request_id: int | None = None,
+) -> None:
+    started_at = time.perf_counter()
+    set_transcribing(True)
+
+    try:
+        LOGGER.info("transcribing file %s", file_path)
+        emit("transcription_progress", id=request_id, stage="decoding", progress=0.0)
+        audio, sample_rate = sf.read(str(file_path), dtype="float32", always_2d=True)
+
+        emit("transcription_progress", id=request_id, stage="resampling", progress=0.0)
+        audio = to_mono_16k(audio, sample_rate)
+        if audio.size == 0:
+            emit("error", id=request_id, message=f"Audio file is empty: {file_path.name}")
+            return
+
+        if cancel_event.is_set():
+            emit("job_cancelled", id=request_id)
+            return
+
+        emit("transcription_progress", id=request_id, stage="transcribing", progress=0.0)
+        transcribe_audio(audio, cancel_event, request_id, started_at)
+    except Exception as exc:
+        emit("error", id=request_id, message=f"File transcription failed: {exc}")
+        LOGGER.exception("file transcription failed")
+    finally:
+        set_transcribing(False)
+
+
+def cancel_current(silent: bool = False, request_id: int | None = None) -> None:
     was_recording = STATE.recording
 
     if STATE.recording:
@@ -407,16 +1057,42 @@ def cancel_current(silent: bool = False) -> None:
         STATE.cancel_event.set()
 
     if (was_recording or (thread and thread.is_alive())) and not silent:
-        emit("job_cancelled")
+        emit("job_cancelled", id=request_id)
+
+
+def preload_model(request_id: int | None = None) -> None:
+    # Loading takes seconds; keep the stdin loop free for healthchecks meanwhile.
+    threading.Thread(target=preload_worker, args=(request_id,), daemon=True).start()
+
+
+def preload_worker(request_id: int | None) -> None:
+    with STATE.audio_lock:
+        busy = STATE.recording
+    with STATE.model_lock:
+        busy = busy or STATE.transcribing
+        switching = STATE.model is not None and STATE.model_name_used != STATE.config.model
+    # Never swap the model under a running job; the next job loads the new one.
+    if busy and switching:
+        LOGGER.info("model switch to '%s' deferred until the current job ends", STATE.config.model)
+        return
+    try:
+        load_model_if_needed(request_id)
+    except Exception as exc:
+        emit("error", id=request_id, message=f"Model preload failed: {exc}")
+        LOGGER.exception("model preload failed")
 
 
-def set_config(config: dict[str, Any]) -> None:
+def set_config(config: dict[str, Any], request_id: int | None = None) -> None:
     lang = config.get("language_mode")
     timeout_sec = config.get("popup_timeout_sec")
     keepalive_min = config.get("model_keepalive_min")
 
     if isinstance(lang, str) and lang:
-        STATE.config.language_mode = lang
+        if lang == AUTO_LANGUAGE or lang in SUPPORTED_LANGUAGES:
+            STATE.config.language_mode = lang
+        else:
+            supported = ", ".join((AUTO_LANGUAGE, *SUPPORTED_LANGUAGES))
+            emit("error", id=request_id, message=f"Unsupported language_mode '{lang}'; supported: {supported}")
 
     if isinstance(timeout_sec, int) and timeout_sec > 0:
         STATE.config.popup_timeout_sec = timeout_sec
@@ -424,43 +1100,141 @@ def set_config(config: dict[str, Any]) -> None:
     if isinstance(keepalive_min, int) and 1 <= keepalive_min <= 240:
         STATE.config.model_keepalive_min = keepalive_min
 
+    streaming_partials = config.get("streaming_partials")
+    if isinstance(streaming_partials, bool):
+        STATE.config.streaming_partials = streaming_partials
+
+    vad_silence_ms = config.get("vad_silence_ms")
+    if isinstance(vad_silence_ms, int) and 0 <= vad_silence_ms <= 60_000:
+        STATE.config.vad_silence_ms = vad_silence_ms
+
+    if "input_device" in config:
+        input_device = config.get("input_device")
+        STATE.config.input_device = input_device if isinstance(input_device, str) else ""
 
-def healthcheck() -> None:
+    if "recordings_dir" in config:
+        recordings_dir = config.get("recordings_dir")
+        STATE.config.recordings_dir = recordings_dir if isinstance(recordings_dir, str) else ""
+
+    model = config.get("model")
+    if isinstance(model, str) and model:
+        if model in MODEL_VARIANTS:
+            STATE.config.model = model
+        else:
+            supported = ", ".join(MODEL_VARIANTS)
+            emit("error", id=request_id, message=f"Unknown model '{model}'; supported: {supported}")
+
+    vad_threshold = config.get("vad_threshold_dbfs")
+    if isinstance(vad_threshold, (int, float)) and -100 <= vad_threshold < 0:
+        STATE.config.vad_threshold_dbfs = float(vad_threshold)
+
+
+def model_cache_dir() -> Path:
+    cache_dir = getattr(gigaam, "_CACHE_DIR", None) if gigaam is not None else None
+    return Path(cache_dir) if cache_dir else GIGAAM_CACHE_DIR
+
+
+def checkpoint_path(name: str) -> Path:
+    """Where `load_gigaam` keeps the checkpoint: gigaam saves `<name>.ckpt` in the download root."""
+    return model_cache_dir() / f"{name}.ckpt"
+
+
+def list_models(request_id: int | None = None) -> None:
+    """Report every known variant; `size_bytes` is set for checkpoints already downloaded."""
+    models = []
+    for name, (decoder, e2e) in MODEL_VARIANTS.items():
+        checkpoint = checkpoint_path(name)
+        size_bytes = checkpoint.stat().st_size if checkpoint.is_file() else None
+        models.append(
+            {
+                "name": name,
+                "decoder": decoder,
+                "e2e": e2e,
+                "downloaded": size_bytes is not None,
+                "size_bytes": size_bytes,
+            }
+        )
+    emit("models", id=request_id, models=models, current=STATE.config.model)
+
+
+def healthcheck(request_id: int | None = None) -> None:
     emit(
         "metrics",
+        id=request_id,
         device=STATE.model_device,
         model=STATE.model_name_used,
         latency_ms=0,
     )
 
 
+def init(cmd: dict[str, Any]) -> None:
+    app_protocol = cmd.get("protocol_version")
+    if app_protocol != PROTOCOL_VERSION:
+        LOGGER.warning("app protocol %s differs from sidecar protocol %s", app_protocol, PROTOCOL_VERSION)
+
+    capabilities = cmd.get("capabilities")
+    if isinstance(capabilities, list):
+        missing = [c for c in capabilities if c not in SUPPORTED_COMMANDS]
+        if missing:
+            LOGGER.warning("app expects unsupported commands: %s", missing)
+
+    emit(
+        "ready",
+        id=cmd.get("id"),
+        device=choose_device(),
+        model=STATE.config.model,
+        protocol_version=PROTOCOL_VERSION,
+        sidecar_version=SIDECAR_VERSION,
+        commands=list(SUPPORTED_COMMANDS),
+        languages=list(SUPPORTED_LANGUAGES),
+        models=list(MODEL_VARIANTS),
+    )
+
+
 def handle_command(cmd: dict[str, Any]) -> None:
     name = cmd.get("command")
+    request_id = cmd.get("id")
 
     if name == "init":
-        emit("ready", device=choose_device(), model=MODEL_NAME)
+        init(cmd)
         return
 
     if name == "start_recording":
-        start_recording()
+        start_recording(request_id)
         return
 
     if name == "stop_and_transcribe":
-        stop_and_transcribe()
+        stop_and_transcribe(request_id)
         return
 
     if name == "cancel_current":
-        cancel_current()
+        cancel_current(request_id=request_id)
         return
 
     if name == "set_config":
         config = cmd.get("config")
         if isinstance(config, dict):
-            set_config(config)
+            set_config(config, request_id)
+        return
+
+    if name == "transcribe_file":
+        transcribe_file(cmd.get("path"), request_id)
+        return
+
+    if name == "list_input_devices":
+        list_input_devices(request_id)
+        return
+
+    if name == "list_models":
+        list_models(request_id)
+        return
+
+    if name == "preload_model":
+        preload_model(request_id)
         return
 
     if name == "healthcheck":
-        healthcheck()
+        healthcheck(request_id)
         return
 
     if name == "shutdown":
@@ -468,7 +1242,7 @@ def handle_command(cmd: dict[str, Any]) -> None:
         STATE.shutdown_event.set()
         return
 
-    emit("error", message=f"Unknown command: {name}")
+    emit("error", id=request_id, message=f"Unknown command: {name}")
 
 
 def run() -> int:
@@ -0,0 +1,268 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::protocol::{ModelInfo, SidecarConfig, SidecarEvent};
+
+/// Picks the mock backend instead of the Python sidecar, e.g. for UI work without a model.
+pub(crate) const BACKEND_ENV_VAR: &str = "SBER_WHISPER_ASR_BACKEND";
+/// Text the mock backend returns for every recording and file.
+pub(crate) const MOCK_TRANSCRIPT_ENV_VAR: &str = "SBER_WHISPER_MOCK_TRANSCRIPT";
+pub(crate) const DEFAULT_MOCK_TRANSCRIPT: &str = "проверка связи";
+
+/// Receives a backend's events together with the request id they answer.
+pub(crate) type EventSink = Arc<dyn Fn(Option<u64>, SidecarEvent) + Send + Sync>;
+
+/// A speech recognition engine. Commands take the request id that the resulting events carry, so
+/// the app can link a request before it is sent; the events themselves arrive through the sink
+/// given to `set_events`, in sidecar protocol form.
+pub(crate) trait AsrBackend: Send + Sync {
+    fn start(&self, id: u64) -> Result<(), String>;
+    fn stop(&self, id: u64) -> Result<(), String>;
+    fn transcribe_file(&self, id: u64, path: &Path) -> Result<(), String>;
+    fn cancel(&self, id: u64) -> Result<(), String>;
+    fn set_config(&self, id: u64, config: &SidecarConfig) -> Result<(), String>;
+    /// Loads the configured model ahead of the first dictation.
+    fn preload_model(&self, id: u64) -> Result<(), String>;
+    /// Blocks until the engine answers with `metrics`, or `error`.
+    fn healthcheck(&self) -> Result<SidecarEvent, String>;
+    /// Blocks until the engine answers with `models`, or `error`.
+    fn list_models(&self) -> Result<SidecarEvent, String>;
+    /// Blocks until the engine answers with `input_devices`, or `error`.
+    fn list_input_devices(&self) -> Result<SidecarEvent, String>;
+    /// Routes all further events to `sink`, replacing the previous one.
+    fn set_events(&self, sink: EventSink);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BackendKind {
+    Sidecar,
+    Mock,
+}
+
+impl BackendKind {
+    /// Reads `SBER_WHISPER_ASR_BACKEND`; anything but "mock" keeps the sidecar.
+    pub fn from_env() -> Self {
+        match std::env::var(BACKEND_ENV_VAR) {
+            Ok(raw) if raw.trim().eq_ignore_ascii_case("mock") => Self::Mock,
+            _ => Self::Sidecar,
+        }
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    recording: bool,
+    /// Model name from the last `set_config`, echoed back as if it were loaded.
+    model: Option<String>,
+    sink: Option<EventSink>,
+}
+
+const MOCK_NAME: &str = "mock";
+
+/// Answers every command at once with fixed events, so the app flow can run without Python.
+pub(crate) struct MockBackend {
+    transcript: String,
+    state: Mutex<MockState>,
+}
+
+impl MockBackend {
+    pub fn new(transcript: impl Into<String>) -> Self {
+        Self {
+            transcript: transcript.into(),
+            state: Mutex::new(MockState::default()),
+        }
+    }
+
+    /// Uses `SBER_WHISPER_MOCK_TRANSCRIPT` when set.
+    pub fn from_env() -> Self {
+        let transcript = std::env::var(MOCK_TRANSCRIPT_ENV_VAR)
+            .ok()
+            .filter(|text| !text.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_MOCK_TRANSCRIPT.to_string());
+        Self::new(transcript)
+    }
+
+    /// Flips the recording flag and returns whether it was set, plus the sink to deliver to.
+    fn begin(&self, recording: bool) -> Result<(bool, Option<EventSink>), String> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| "failed to lock mock backend mutex".to_string())?;
+        let was_recording = std::mem::replace(&mut state.recording, recording);
+        Ok((was_recording, state.sink.clone()))
+    }
+
+    fn model(&self) -> String {
+        self.state
+            .lock()
+            .ok()
+            .and_then(|state| state.model.clone())
+            .unwrap_or_else(|| MOCK_NAME.to_string())
+    }
+
+    fn metrics(&self) -> SidecarEvent {
+        SidecarEvent::Metrics {
+            latency_ms: 0,
+            device: MOCK_NAME.to_string(),
+            model: self.model(),
+        }
+    }
+
+    fn transcript_events(&self) -> Vec<SidecarEvent> {
+        vec![
+            SidecarEvent::FinalTranscript {
+                text: self.transcript.clone(),
+            },
+            self.metrics(),
+        ]
+    }
+}
+
+/// Delivers outside the state lock, so a sink may call back into the backend.
+fn deliver(sink: Option<EventSink>, id: u64, events: Vec<SidecarEvent>) {
+    if let Some(sink) = sink {
+        for event in events {
+            sink(Some(id), event);
+        }
+    }
+}
+
+impl AsrBackend for MockBackend {
+    fn start(&self, id: u64) -> Result<(), String> {
+        let (_, sink) = self.begin(true)?;
+        deliver(sink, id, vec![SidecarEvent::RecordingStarted]);
+        Ok(())
+    }
+
+    fn stop(&self, id: u64) -> Result<(), String> {
+        let (was_recording, sink) = self.begin(false)?;
+        let events = if was_recording {
+            let mut events = vec![SidecarEvent::RecordingStopped];
+            events.extend(self.transcript_events());
+            events
+        } else {
+            vec![SidecarEvent::error("Recording is not active")]
+        };
+        deliver(sink, id, events);
+        Ok(())
+    }
+
+    fn transcribe_file(&self, id: u64, _path: &Path) -> Result<(), String> {
+        let (_, sink) = self.begin(false)?;
+        deliver(sink, id, self.transcript_events());
+        Ok(())
+    }
+
+    fn cancel(&self, id: u64) -> Result<(), String> {
+        let (_, sink) = self.begin(false)?;
+        deliver(sink, id, vec![SidecarEvent::JobCancelled]);
+        Ok(())
+    }
+
+    fn set_config(&self, _id: u64, config: &SidecarConfig) -> Result<(), String> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| "failed to lock mock backend mutex".to_string())?;
+        state.model = Some(config.model.clone()).filter(|model| !model.trim().is_empty());
+        Ok(())
+    }
+
+    fn preload_model(&self, _id: u64) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn healthcheck(&self) -> Result<SidecarEvent, String> {
+        Ok(self.metrics())
+    }
+
+    /// Lists the configured model as the only one, already downloaded.
+    fn list_models(&self) -> Result<SidecarEvent, String> {
+        let current = self.model();
+        Ok(SidecarEvent::Models {
+            models: vec![ModelInfo {
+                name: current.clone(),
+                decoder: "ctc".to_string(),
+                e2e: false,
+                downloaded: true,
+                size_bytes: None,
+            }],
+            current,
+        })
+    }
+
+    fn list_input_devices(&self) -> Result<SidecarEvent, String> {
+        Ok(SidecarEvent::InputDevices {
+            devices: Vec::new(),
+            default_id: None,
+        })
+    }
+
+    fn set_events(&self, sink: EventSink) {
+        if let Ok(mut state) = self.state.lock() {
+            state.sink = Some(sink);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    use super::{AsrBackend, MockBackend};
+    use crate::protocol::{SidecarConfig, SidecarEvent};
+
+    type Recorded = Arc<Mutex<Vec<(Option<u64>, SidecarEvent)>>>;
+
+    fn recorded_backend() -> (MockBackend, Recorded) {
+        let backend = MockBackend::new("привет мир");
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink_events = Arc::clone(&events);
+        backend.set_events(Arc::new(move |id, event| {
+            sink_events.lock().unwrap().push((id, event));
+        }));
+        (backend, events)
+    }
+
+    #[test]
+    fn mock_answers_files_cancel_and_stray_stops() {
+        let (backend, events) = recorded_backend();
+        backend.stop(1).unwrap();
+        backend.transcribe_file(2, Path::new("note.wav")).unwrap();
+        backend.start(3).unwrap();
+        backend.cancel(4).unwrap();
+
+        let events = events.lock().unwrap();
+        assert!(matches!(events[0], (Some(1), SidecarEvent::Error { .. })));
+        assert!(matches!(events[1].1, SidecarEvent::FinalTranscript { .. }));
+        assert!(matches!(events[2].1, SidecarEvent::Metrics { .. }));
+        assert_eq!(events[3], (Some(3), SidecarEvent::RecordingStarted));
+        assert_eq!(events[4], (Some(4), SidecarEvent::JobCancelled));
+    }
+
+    #[test]
+    fn mock_reports_the_configured_model() {
+        let (backend, _) = recorded_backend();
+        let config: SidecarConfig = serde_json::from_value(serde_json::json!({
+            "language_mode": "ru",
+            "popup_timeout_sec": 10,
+            "model_keepalive_min": 5,
+            "model": "v2_ctc",
+        }))
+        .unwrap();
+        backend.set_config(1, &config).unwrap();
+
+        let SidecarEvent::Models { models, current } = backend.list_models().unwrap() else {
+            panic!("expected a model list");
+        };
+        assert_eq!(current, "v2_ctc");
+        assert!(models
+            .iter()
+            .all(|model| model.name == "v2_ctc" && model.downloaded));
+        assert!(matches!(
+            backend.healthcheck().unwrap(),
+            SidecarEvent::Metrics { model, .. } if model == "v2_ctc"
+        ));
+    }
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::segments::TranscriptSegment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ExportFormat {
+    Srt,
+    Vtt,
+    Txt,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Txt => "txt",
+            Self::Json => "json",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Srt => "SubRip subtitles",
+            Self::Vtt => "WebVTT subtitles",
+            Self::Txt => "Plain text",
+            Self::Json => "JSON",
+        }
+    }
+}
+
+/// Renders a transcript in `format`. Subtitle formats need the segments' timing: a transcript
+/// without segments is refused rather than exported as a zero-length cue.
+pub(crate) fn render_transcript(
+    format: ExportFormat,
+    text: &str,
+    segments: &[TranscriptSegment],
+) -> Result<String, String> {
+    let cues = cues(segments);
+    let subtitles = matches!(format, ExportFormat::Srt | ExportFormat::Vtt);
+    if subtitles && cues.is_empty() && !text.trim().is_empty() {
+        return Err("this transcript has no timing; export it as TXT or JSON".to_string());
+    }
+    match format {
+        ExportFormat::Txt => Ok(format!("{}\n", text.trim())),
+        ExportFormat::Json => {
+            let value = json!({
+                "text": text.trim(),
+                "segments": cues,
+            });
+            serde_json::to_string_pretty(&value)
+                .map(|json| json + "\n")
+                .map_err(|e| format!("failed to serialize transcript: {e}"))
+        }
+        ExportFormat::Srt => Ok(cues
+            .iter()
+            .enumerate()
+            .map(|(i, cue)| {
+                format!(
+                    "{}\n{} --> {}\n{}\n",
+                    i + 1,
+                    format_timestamp(cue.start_ms, ','),
+                    format_timestamp(cue.end_ms, ','),
+                    cue.text
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")),
+        ExportFormat::Vtt => {
+            let mut out = String::from("WEBVTT\n");
+            for cue in cues {
+                out.push_str(&format!(
+                    "\n{} --> {}\n{}\n",
+                    format_timestamp(cue.start_ms, '.'),
+                    format_timestamp(cue.end_ms, '.'),
+                    cue.text
+                ));
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// `HH:MM:SS<sep>mmm`; SubRip uses `,` and WebVTT `.` before the milliseconds.
+pub(crate) fn format_timestamp(ms: u64, separator: char) -> String {
+    let hours = ms / 3_600_000;
+    let minutes = ms / 60_000 % 60;
+    let seconds = ms / 1_000 % 60;
+    let millis = ms % 1_000;
+    format!("{hours:02}:{minutes:02}:{seconds:02}{separator}{millis:03}")
+}
+
+/// Segments ready for output: in order, without empty text, and clamped so no cue starts
+/// before the previous one ends.
+fn cues(segments: &[TranscriptSegment]) -> Vec<TranscriptSegment> {
+    let mut ordered: Vec<&TranscriptSegment> = segments.iter().collect();
+    ordered.sort_by_key(|segment| (segment.start_ms, segment.index));
+
+    let mut previous_end = 0;
+    let mut cues: Vec<TranscriptSegment> = Vec::new();
+    for segment in ordered {
+        let text = segment.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let start_ms = segment.start_ms.max(previous_end);
+        let end_ms = segment.end_ms.max(start_ms);
+        previous_end = end_ms;
+        cues.push(TranscriptSegment {
+            index: cues.len() as u32,
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+        });
+    }
+    cues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_timestamp, render_transcript, ExportFormat};
+    use crate::segments::TranscriptSegment;
+
+    fn segment(index: u32, start_ms: u64, end_ms: u64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            index,
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(format_timestamp(0, ','), "00:00:00,000");
+        assert_eq!(format_timestamp(61_005, ','), "00:01:01,005");
+        assert_eq!(format_timestamp(3_723_450, '.'), "01:02:03.450");
+        assert_eq!(format_timestamp(100 * 3_600_000, '.'), "100:00:00.000");
+    }
+
+    #[test]
+    fn renders_subtitles_with_overlapping_and_empty_segments() {
+        let segments = [
+            segment(1, 19_000, 39_500, "в офисе"),
+            segment(0, 0, 20_000, "встретимся утром"),
+            segment(2, 39_000, 41_000, "  "),
+        ];
+        let srt =
+            render_transcript(ExportFormat::Srt, "встретимся утром в офисе", &segments).unwrap();
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:20,000\nвстретимся утром\n\n\
+             2\n00:00:20,000 --> 00:00:39,500\nв офисе\n"
+        );
+
+        let vtt = render_transcript(ExportFormat::Vtt, "", &segments).unwrap();
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:20.000\n"));
+        assert_eq!(vtt.matches(" --> ").count(), 2);
+    }
+
+    #[test]
+    fn handles_empty_text_and_missing_segments() {
+        assert_eq!(render_transcript(ExportFormat::Srt, "  ", &[]).unwrap(), "");
+        assert_eq!(
+            render_transcript(ExportFormat::Vtt, "", &[]).unwrap(),
+            "WEBVTT\n"
+        );
+        assert_eq!(render_transcript(ExportFormat::Txt, "", &[]).unwrap(), "\n");
+
+        let err = render_transcript(ExportFormat::Srt, "без таймингов", &[]).unwrap_err();
+        assert!(err.contains("no timing"), "{err}");
+        assert!(render_transcript(ExportFormat::Vtt, "без таймингов", &[]).is_err());
+
+        let json = render_transcript(ExportFormat::Json, "привет", &[]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["text"], "привет");
+        assert_eq!(value["segments"], serde_json::json!([]));
+    }
+}
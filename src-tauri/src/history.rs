@@ -0,0 +1,381 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+pub(crate) const HISTORY_FILE_NAME: &str = "history.jsonl";
+pub(crate) const DEFAULT_HISTORY_LIMIT: usize = 500;
+/// Lines of dropped entries the file may hold before retention rewrites it.
+const RETENTION_REWRITE_SLACK: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct HistoryEntry {
+    pub id: u64,
+    pub text: String,
+    pub timestamp_ms: i64,
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+    #[serde(default)]
+    pub device: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Stored recording this transcript came from, when audio is kept.
+    #[serde(default)]
+    pub audio_path: Option<String>,
+}
+
+/// Append-only JSONL transcript history. The file is only rewritten when entries are removed:
+/// right away on delete and clear, and for retention once `RETENTION_REWRITE_SLACK` dropped
+/// entries have piled up, so a full history is not rewritten on every dictation. Entries already
+/// dropped from memory are dropped again when the file is opened.
+///
+/// A transcript arrives before its `metrics` event, so it is held as pending until the metrics
+/// for the same request come in (or the next transcript starts) and only then appended.
+pub(crate) struct HistoryStore {
+    path: PathBuf,
+    entries: Vec<HistoryEntry>,
+    pending: Option<(Option<u64>, HistoryEntry)>,
+    next_id: u64,
+    limit: usize,
+    /// Lines in the file, including entries retention dropped since the last rewrite.
+    file_lines: usize,
+    /// Recordings of removed entries that no other entry links to.
+    dropped_audio: Vec<String>,
+}
+
+impl HistoryStore {
+    /// Loads the history file, skipping lines that fail to parse.
+    pub fn open(path: PathBuf, limit: usize) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        let mut file_lines = 0;
+        if path.exists() {
+            let file = File::open(&path).map_err(|e| format!("failed to open history: {e}"))?;
+            for line in BufReader::new(file).lines().map_while(Result::ok) {
+                file_lines += 1;
+                if let Ok(entry) = serde_json::from_str::<HistoryEntry>(&line) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        let next_id = entries.iter().map(|e| e.id).max().unwrap_or(0) + 1;
+        let mut store = Self {
+            path,
+            entries,
+            pending: None,
+            next_id,
+            limit,
+            file_lines,
+            dropped_audio: Vec::new(),
+        };
+        store.apply_retention()?;
+        Ok(store)
+    }
+
+    pub fn set_limit(&mut self, limit: usize) -> Result<(), String> {
+        self.limit = limit;
+        self.apply_retention()
+    }
+
+    /// Starts a history entry for a final transcript of request `request_id`.
+    pub fn begin(
+        &mut self,
+        request_id: Option<u64>,
+        text: &str,
+        timestamp_ms: i64,
+    ) -> Result<(), String> {
+        self.flush_pending()?;
+        if self.limit == 0 || text.trim().is_empty() {
+            return Ok(());
+        }
+
+        let entry = HistoryEntry {
+            id: self.next_id,
+            text: text.to_string(),
+            timestamp_ms,
+            latency_ms: None,
+            device: None,
+            model: None,
+            audio_path: None,
+        };
+        self.next_id += 1;
+        self.pending = Some((request_id, entry));
+        Ok(())
+    }
+
+    /// Completes the pending entry with the metrics of the same request and writes it.
+    pub fn attach_metrics(
+        &mut self,
+        request_id: Option<u64>,
+        latency_ms: u64,
+        device: &str,
+        model: &str,
+    ) -> Result<(), String> {
+        match &mut self.pending {
+            Some((pending_id, entry)) if *pending_id == request_id => {
+                entry.latency_ms = Some(latency_ms);
+                entry.device = Some(device.to_string());
+                entry.model = Some(model.to_string());
+            }
+            _ => return Ok(()),
+        }
+        self.flush_pending()
+    }
+
+    /// Links the pending entry of `request_id` to its stored recording.
+    pub fn attach_audio(&mut self, request_id: Option<u64>, audio_path: &str) {
+        if let Some((pending_id, entry)) = &mut self.pending {
+            if *pending_id == request_id {
+                entry.audio_path = Some(audio_path.to_string());
+            }
+        }
+    }
+
+    /// Whether any entry still links to `audio_path`.
+    pub fn references_audio(&self, audio_path: &str) -> bool {
+        self.newest_first()
+            .any(|entry| entry.audio_path.as_deref() == Some(audio_path))
+    }
+
+    /// Recordings that only dropped entries linked to; the caller deletes the files.
+    pub fn take_dropped_audio(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.dropped_audio)
+    }
+
+    pub fn flush_pending(&mut self) -> Result<(), String> {
+        let Some((_, entry)) = self.pending.take() else {
+            return Ok(());
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("failed to open history: {e}"))?;
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| format!("failed to serialize history entry: {e}"))?;
+        writeln!(file, "{line}").map_err(|e| format!("failed to write history: {e}"))?;
+
+        self.file_lines += 1;
+        self.entries.push(entry);
+        self.apply_retention()
+    }
+
+    /// Newest first, including a transcript still waiting for its metrics.
+    pub fn list(&self, limit: Option<usize>) -> Vec<HistoryEntry> {
+        self.newest_first()
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    /// Case-insensitive substring search, newest first.
+    pub fn search(&self, query: &str) -> Vec<HistoryEntry> {
+        let query = query.trim().to_lowercase();
+        self.newest_first()
+            .filter(|entry| entry.text.to_lowercase().contains(&query))
+            .cloned()
+            .collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&HistoryEntry> {
+        self.newest_first().find(|entry| entry.id == id)
+    }
+
+    /// Removes entry `id`; its recording is handed back if no other entry links to it.
+    pub fn delete(&mut self, id: u64) -> Result<bool, String> {
+        if matches!(&self.pending, Some((_, entry)) if entry.id == id) {
+            let (_, entry) = self.pending.take().expect("pending entry was just matched");
+            self.drop_audio(vec![entry]);
+            return Ok(true);
+        }
+        let Some(index) = self.entries.iter().position(|entry| entry.id == id) else {
+            return Ok(false);
+        };
+        let entry = self.entries.remove(index);
+        self.drop_audio(vec![entry]);
+        self.rewrite()?;
+        Ok(true)
+    }
+
+    pub fn clear(&mut self) -> Result<(), String> {
+        let mut removed: Vec<HistoryEntry> = self.entries.drain(..).collect();
+        removed.extend(self.pending.take().map(|(_, entry)| entry));
+        self.drop_audio(removed);
+        self.rewrite()
+    }
+
+    fn newest_first(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.pending
+            .iter()
+            .map(|(_, entry)| entry)
+            .chain(self.entries.iter().rev())
+    }
+
+    fn apply_retention(&mut self) -> Result<(), String> {
+        if self.entries.len() > self.limit {
+            let excess = self.entries.len() - self.limit;
+            let dropped: Vec<HistoryEntry> = self.entries.drain(..excess).collect();
+            self.drop_audio(dropped);
+        }
+        if self.file_lines > self.entries.len() + RETENTION_REWRITE_SLACK {
+            self.rewrite()?;
+        }
+        Ok(())
+    }
+
+    fn drop_audio(&mut self, removed: Vec<HistoryEntry>) {
+        for audio_path in removed.into_iter().filter_map(|entry| entry.audio_path) {
+            if !self.references_audio(&audio_path) && !self.dropped_audio.contains(&audio_path) {
+                self.dropped_audio.push(audio_path);
+            }
+        }
+    }
+
+    fn rewrite(&mut self) -> Result<(), String> {
+        let tmp_path = self.path.with_extension("jsonl.tmp");
+        let mut file =
+            File::create(&tmp_path).map_err(|e| format!("failed to create history file: {e}"))?;
+        for entry in &self.entries {
+            let line = serde_json::to_string(entry)
+                .map_err(|e| format!("failed to serialize history entry: {e}"))?;
+            writeln!(file, "{line}").map_err(|e| format!("failed to write history: {e}"))?;
+        }
+        drop(file);
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("failed to replace history file: {e}"))?;
+        self.file_lines = self.entries.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{HistoryStore, RETENTION_REWRITE_SLACK};
+
+    fn temp_history(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "sber-whisper-history-{}-{name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("history.jsonl")
+    }
+
+    #[test]
+    fn transcript_waits_for_its_metrics_and_survives_reload() {
+        let path = temp_history("reload");
+        let mut store = HistoryStore::open(path.clone(), 10).unwrap();
+        store.begin(Some(7), "Привет мир", 1_000).unwrap();
+        store.attach_audio(Some(7), "/rec/1.flac");
+        assert_eq!(store.list(None).len(), 1);
+        assert!(!path.exists());
+
+        store.attach_metrics(Some(6), 1, "cpu", "other").unwrap();
+        assert!(!path.exists());
+        store
+            .attach_metrics(Some(7), 420, "cuda", "v2_ctc")
+            .unwrap();
+
+        let reopened = HistoryStore::open(path, 10).unwrap();
+        let entries = reopened.list(None);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].latency_ms, Some(420));
+        assert_eq!(entries[0].device.as_deref(), Some("cuda"));
+        assert_eq!(entries[0].audio_path.as_deref(), Some("/rec/1.flac"));
+        assert!(reopened.references_audio("/rec/1.flac"));
+        assert_eq!(reopened.search("ПРИВЕТ").len(), 1);
+        assert!(reopened.search("пока").is_empty());
+    }
+
+    #[test]
+    fn retention_delete_and_clear() {
+        let path = temp_history("retention");
+        let mut store = HistoryStore::open(path.clone(), 2).unwrap();
+        for (i, text) in ["one", "two", "three"].iter().enumerate() {
+            store.begin(None, text, i as i64).unwrap();
+        }
+        store.flush_pending().unwrap();
+
+        let texts: Vec<String> = store.list(None).into_iter().map(|e| e.text).collect();
+        assert_eq!(texts, vec!["three", "two"]);
+
+        let id = store.list(Some(1))[0].id;
+        assert!(store.delete(id).unwrap());
+        assert!(!store.delete(id).unwrap());
+        assert_eq!(
+            HistoryStore::open(path.clone(), 2)
+                .unwrap()
+                .list(None)
+                .len(),
+            1
+        );
+
+        store.clear().unwrap();
+        assert!(HistoryStore::open(path, 2).unwrap().list(None).is_empty());
+    }
+
+    #[test]
+    fn retention_hands_back_recordings_no_entry_links_to() {
+        let path = temp_history("dropped-audio");
+        let mut store = HistoryStore::open(path, 2).unwrap();
+        for (i, audio) in ["/rec/1.flac", "/rec/2.flac", "/rec/2.flac"]
+            .iter()
+            .enumerate()
+        {
+            store.begin(Some(i as u64), "text", i as i64).unwrap();
+            store.attach_audio(Some(i as u64), audio);
+        }
+        store.flush_pending().unwrap();
+        assert_eq!(store.take_dropped_audio(), vec!["/rec/1.flac"]);
+
+        // A retranscribed recording stays while a newer entry still links to it.
+        store.set_limit(1).unwrap();
+        assert!(store.take_dropped_audio().is_empty());
+        store.set_limit(0).unwrap();
+        assert_eq!(store.take_dropped_audio(), vec!["/rec/2.flac"]);
+
+        store.set_limit(3).unwrap();
+        for (i, audio) in ["/rec/3.flac", "/rec/3.flac", "/rec/4.flac"]
+            .iter()
+            .enumerate()
+        {
+            store.begin(Some(i as u64), "text", i as i64).unwrap();
+            store.attach_audio(Some(i as u64), audio);
+        }
+        let ids: Vec<u64> = store.list(None).into_iter().map(|e| e.id).collect();
+        assert!(store.delete(ids[2]).unwrap());
+        assert!(store.take_dropped_audio().is_empty());
+        assert!(store.delete(ids[0]).unwrap());
+        assert_eq!(store.take_dropped_audio(), vec!["/rec/4.flac"]);
+        store.clear().unwrap();
+        assert_eq!(store.take_dropped_audio(), vec!["/rec/3.flac"]);
+    }
+
+    #[test]
+    fn retention_rewrites_the_file_in_batches() {
+        let path = temp_history("batches");
+        let line_count = |path: &PathBuf| std::fs::read_to_string(path).unwrap().lines().count();
+        let mut store = HistoryStore::open(path.clone(), 2).unwrap();
+        for i in 0..RETENTION_REWRITE_SLACK + 2 {
+            store.begin(None, "text", i as i64).unwrap();
+        }
+        store.flush_pending().unwrap();
+        assert_eq!(line_count(&path), RETENTION_REWRITE_SLACK + 2);
+        assert_eq!(
+            HistoryStore::open(path.clone(), 2)
+                .unwrap()
+                .list(None)
+                .len(),
+            2
+        );
+
+        store.begin(None, "text", 0).unwrap();
+        store.flush_pending().unwrap();
+        assert_eq!(line_count(&path), 2);
+    }
+}
@@ -0,0 +1,79 @@
+/// Lets the sidecar pick the language its model transcribes.
+pub(crate) const AUTO_LANGUAGE: &str = "auto";
+
+/// Assumed for sidecars that do not advertise languages in `ready`; GigaAM is Russian only.
+pub(crate) const FALLBACK_LANGUAGES: &[&str] = &["ru"];
+
+/// The languages a sidecar reported, or the fallback when it reported none.
+pub(crate) fn advertised_languages(reported: &[String]) -> Vec<String> {
+    if reported.is_empty() {
+        FALLBACK_LANGUAGES.iter().map(|l| l.to_string()).collect()
+    } else {
+        reported.to_vec()
+    }
+}
+
+/// Accepts "auto" or one of the advertised languages.
+pub(crate) fn validate_language_mode(mode: &str, languages: &[String]) -> Result<(), String> {
+    if mode == AUTO_LANGUAGE || languages.iter().any(|l| l == mode) {
+        return Ok(());
+    }
+    Err(format!(
+        "language mode '{mode}' is not supported by the ASR sidecar; choose {AUTO_LANGUAGE} or one of: {}",
+        languages.join(", ")
+    ))
+}
+
+/// The language transcripts are in: "auto" means the sidecar's first language.
+pub(crate) fn resolve_language(mode: &str, languages: &[String]) -> String {
+    if mode != AUTO_LANGUAGE {
+        return mode.to_string();
+    }
+    languages
+        .first()
+        .cloned()
+        .unwrap_or_else(|| FALLBACK_LANGUAGES[0].to_string())
+}
+
+/// Language-specific post-processing; stages not listed here work for any language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LanguageProfile {
+    /// Spoken numbers are parsed from Russian words.
+    pub spoken_numbers: bool,
+    /// The built-in spoken command table is Russian.
+    pub default_commands: bool,
+}
+
+impl LanguageProfile {
+    pub fn for_language(language: &str) -> Self {
+        let russian = language == "ru";
+        Self {
+            spoken_numbers: russian,
+            default_commands: russian,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{advertised_languages, resolve_language, validate_language_mode, LanguageProfile};
+
+    #[test]
+    fn validates_and_resolves_against_advertised_languages() {
+        let old_sidecar = advertised_languages(&[]);
+        assert_eq!(old_sidecar, vec!["ru"]);
+        assert!(validate_language_mode("ru", &old_sidecar).is_ok());
+        assert!(validate_language_mode("auto", &old_sidecar).is_ok());
+        let err = validate_language_mode("en", &old_sidecar).unwrap_err();
+        assert!(err.contains("'en'") && err.contains("ru"), "{err}");
+        assert!(validate_language_mode("", &old_sidecar).is_err());
+
+        let multi = advertised_languages(&["en".to_string(), "ru".to_string()]);
+        assert!(validate_language_mode("en", &multi).is_ok());
+        assert_eq!(resolve_language("auto", &multi), "en");
+        assert_eq!(resolve_language("ru", &multi), "ru");
+
+        assert!(LanguageProfile::for_language("ru").spoken_numbers);
+        assert!(!LanguageProfile::for_language("en").default_commands);
+    }
+}
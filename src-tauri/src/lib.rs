@@ -1,26 +1,76 @@
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Mutex;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use arboard::Clipboard;
 use chrono::Local;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
 use tauri::menu::{Menu, MenuItem};
 use tauri::tray::TrayIconBuilder;
 use tauri::{AppHandle, Emitter, Manager, Runtime, WebviewWindow};
 use tauri_plugin_autostart::{MacosLauncher, ManagerExt as _};
+use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
+mod backend;
+mod export;
+mod history;
+mod language;
+mod normalize;
+mod output;
+mod partials;
+mod protocol;
+mod recording;
+mod recordings;
+mod replacements;
+mod segments;
+mod shortcuts;
+mod spoken;
+
+use backend::{AsrBackend, BackendKind, EventSink, MockBackend};
+use export::{render_transcript, ExportFormat};
+use history::{HistoryEntry, HistoryStore, DEFAULT_HISTORY_LIMIT, HISTORY_FILE_NAME};
+use language::{advertised_languages, resolve_language, validate_language_mode, LanguageProfile};
+use normalize::{NormalizationSettings, Pipeline};
+use output::{
+    deliver_text, ClipboardKeeper, ClipboardOutcome, OutputMode, SystemTextSink, TextSinkFactory,
+};
+use partials::{PartialDecision, PartialThrottle};
+use protocol::{
+    advertised_models, parse_event, InputDevice, ModelInfo, PendingRequests, ProtocolError,
+    SidecarCommand, SidecarCompatibility, SidecarConfig, SidecarEvent, SidecarMessage,
+};
+use recording::{HotkeyAction, HotkeyGesture, RecordingMode, VadSettings};
+use recordings::{apply_retention, RetentionPolicy, RECORDINGS_DIR_NAME};
+use replacements::{load_rules, save_rules, ReplacementRule, Replacer, REPLACEMENTS_FILE_NAME};
+use segments::{SegmentAggregator, TranscriptSegment};
+use shortcuts::{validate_keymap, ShortcutAction, ShortcutBinding, ShortcutRegistration};
+use spoken::{default_commands, SpokenCommand, SpokenCommandSettings, SpokenCommands};
+
 const SETTINGS_FILE_NAME: &str = "app_settings.json";
 const APP_LOG_NAME: &str = "app.log";
 const LOG_ROTATE_SIZE_BYTES: u64 = 2 * 1024 * 1024;
 const TRAY_ICON: tauri::image::Image<'_> = tauri::include_image!("./icons/32x32.png");
+const TRAY_ID: &str = "main";
+const HEALTHCHECK_TIMEOUT: Duration = Duration::from_secs(3);
+const DEVICE_LIST_TIMEOUT: Duration = Duration::from_secs(5);
+const MODEL_LIST_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_MODEL: &str = "v3_e2e_rnnt";
+const SIDECAR_WATCHDOG_INTERVAL: Duration = Duration::from_secs(10);
+const SIDECAR_PING_TIMEOUT: Duration = Duration::from_secs(5);
+const SIDECAR_MAX_MISSED_PINGS: u32 = 2;
+const SIDECAR_RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+const SIDECAR_RESTART_MAX_DELAY: Duration = Duration::from_secs(30);
+const SIDECAR_MAX_RESTARTS: u32 = 5;
+const SIDECAR_STABLE_UPTIME: Duration = Duration::from_secs(60);
+const SUPPORTED_AUDIO_EXTENSIONS: &[&str] = &["wav", "flac", "ogg"];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AppSettings {
@@ -30,6 +80,67 @@ struct AppSettings {
     auto_launch: bool,
     language_mode: String,
     theme: String,
+    /// Load the model right after the sidecar starts instead of on first dictation.
+    #[serde(default)]
+    preload_model: bool,
+    #[serde(default)]
+    recording_mode: RecordingMode,
+    /// Extra shortcuts on top of the dictation `hotkey`.
+    #[serde(default)]
+    keymap: Vec<ShortcutBinding>,
+    #[serde(default)]
+    output_mode: OutputMode,
+    /// After pasting, put back whatever was on the clipboard before.
+    #[serde(default)]
+    restore_clipboard: bool,
+    /// Put the previous clipboard contents back this many seconds after a transcript; 0 = never.
+    #[serde(default)]
+    clipboard_restore_sec: u64,
+    /// Number of transcripts kept in history; 0 turns history off.
+    #[serde(default = "default_history_limit")]
+    history_limit: usize,
+    /// Show text in the popup while still speaking; costs extra CPU/GPU during recording.
+    #[serde(default)]
+    streaming_partials: bool,
+    /// Stop recording by itself once the speaker goes quiet.
+    #[serde(default)]
+    vad: VadSettings,
+    /// Microphone name as listed by `list_input_devices`; `None` records from the default.
+    #[serde(default)]
+    input_device: Option<String>,
+    /// Store every recording next to its history entry.
+    #[serde(default)]
+    keep_audio: bool,
+    /// Stored recordings are trimmed to this total size (MB) and age (days); 0 = unlimited.
+    #[serde(default = "default_audio_retention_mb")]
+    audio_retention_mb: u64,
+    #[serde(default = "default_audio_retention_days")]
+    audio_retention_days: u64,
+    /// Whitespace, capitalization, punctuation and number clean-up of final transcripts.
+    #[serde(default)]
+    normalization: NormalizationSettings,
+    /// Phrases like "новая строка" or "запятая" that turn into text or actions.
+    #[serde(default)]
+    spoken_commands: SpokenCommandSettings,
+    /// GigaAM variant; lighter ones trade accuracy for speed on weaker machines.
+    #[serde(default = "default_model")]
+    model: String,
+}
+
+fn default_history_limit() -> usize {
+    DEFAULT_HISTORY_LIMIT
+}
+
+fn default_model() -> String {
+    DEFAULT_MODEL.to_string()
+}
+
+fn default_audio_retention_mb() -> u64 {
+    1024
+}
+
+fn default_audio_retention_days() -> u64 {
+    30
 }
 
 impl Default for AppSettings {
@@ -46,6 +157,22 @@ impl Default for AppSettings {
             auto_launch: false,
             language_mode: "ru".to_string(),
             theme: "siri_aurora".to_string(),
+            preload_model: false,
+            recording_mode: RecordingMode::Hold,
+            keymap: Vec::new(),
+            output_mode: OutputMode::Clipboard,
+            restore_clipboard: false,
+            clipboard_restore_sec: 0,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            streaming_partials: false,
+            vad: VadSettings::default(),
+            input_device: None,
+            keep_audio: false,
+            audio_retention_mb: default_audio_retention_mb(),
+            audio_retention_days: default_audio_retention_days(),
+            normalization: NormalizationSettings::default(),
+            spoken_commands: SpokenCommandSettings::default(),
+            model: default_model(),
         }
     }
 }
@@ -65,14 +192,105 @@ struct LegacySettings {
 struct SidecarProcess {
     child: Child,
     stdin: ChildStdin,
+    started_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SidecarStatus {
+    Starting,
+    Ready,
+    Degraded,
+    Failed,
+}
+
+impl SidecarStatus {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Starting => "Starting",
+            Self::Ready => "Ready",
+            Self::Degraded => "Degraded",
+            Self::Failed => "Failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct SidecarStatusReport {
+    status: SidecarStatus,
+    detail: Option<String>,
+    model_loading: bool,
+}
+
+/// Exponential restart delays, giving up after `SIDECAR_MAX_RESTARTS` in a row.
+#[derive(Debug, Default)]
+struct RestartBackoff {
+    attempts: u32,
+}
+
+impl RestartBackoff {
+    fn next_delay(&mut self) -> Option<Duration> {
+        if self.attempts >= SIDECAR_MAX_RESTARTS {
+            return None;
+        }
+        let delay = SIDECAR_RESTART_BASE_DELAY
+            .saturating_mul(1 << self.attempts.min(16))
+            .min(SIDECAR_RESTART_MAX_DELAY);
+        self.attempts += 1;
+        Some(delay)
+    }
+
+    fn reset(&mut self) {
+        self.attempts = 0;
+    }
+}
+
+enum SidecarProbe {
+    Running {
+        uptime: Duration,
+    },
+    /// Sidecar exited on its own after the keepalive timeout; restarts lazily.
+    IdleReleased,
+    Exited,
 }
 
 struct SharedState {
     settings: Mutex<AppSettings>,
     sidecar: Mutex<Option<SidecarProcess>>,
+    sidecar_compat: Mutex<SidecarCompatibility>,
+    requests: PendingRequests,
+    sidecar_status: Mutex<SidecarStatusReport>,
+    supervisor_wake: Mutex<Option<Sender<()>>>,
+    tray_status_item: Mutex<Option<MenuItem<tauri::Wry>>>,
+    idle_released: AtomicBool,
+    hotkey_gesture: Mutex<HotkeyGesture>,
+    shortcut_actions: Mutex<HashMap<u32, ShortcutAction>>,
+    shortcut_registrations: Mutex<Vec<ShortcutRegistration>>,
+    last_transcript: Mutex<Option<String>>,
+    clipboard_keeper: Mutex<ClipboardKeeper>,
+    /// Where transcripts are delivered: the system clipboard and keyboard outside tests.
+    text_sink: TextSinkFactory,
+    history: Mutex<Option<HistoryStore>>,
+    segments: Mutex<SegmentAggregator>,
+    partials: Mutex<PartialThrottle>,
+    /// Recording stored for a request, linked to its history entry once the transcript arrives.
+    saved_recording: Mutex<Option<(Option<u64>, String)>>,
+    replacer: Mutex<Replacer>,
+    /// Languages from the last `ready`, used to validate `language_mode`.
+    sidecar_languages: Mutex<Vec<String>>,
+    /// Model names from the last `ready`, used to validate `model`.
+    sidecar_models: Mutex<Vec<String>>,
+    /// Engine behind the recording and transcription commands; set during setup.
+    backend: Mutex<Option<Arc<dyn AsrBackend>>>,
+    /// Where the stdout reader delivers sidecar events.
+    sidecar_events: Mutex<Option<EventSink>>,
+    /// Set when another backend replaces the sidecar, which is then never started.
+    sidecar_disabled: AtomicBool,
     recording_started: AtomicBool,
     suppress_disconnect_error: AtomicBool,
     shutdown: AtomicBool,
+    /// Overrides the platform config dir; tests point it at a scratch directory.
+    config_dir: Option<PathBuf>,
 }
 
 impl SharedState {
@@ -80,14 +298,41 @@ impl SharedState {
         Self {
             settings: Mutex::new(settings),
             sidecar: Mutex::new(None),
+            sidecar_compat: Mutex::new(SidecarCompatibility::Unknown),
+            requests: PendingRequests::default(),
+            sidecar_status: Mutex::new(SidecarStatusReport {
+                status: SidecarStatus::Starting,
+                detail: None,
+                model_loading: false,
+            }),
+            supervisor_wake: Mutex::new(None),
+            tray_status_item: Mutex::new(None),
+            idle_released: AtomicBool::new(false),
+            hotkey_gesture: Mutex::new(HotkeyGesture::default()),
+            shortcut_actions: Mutex::new(HashMap::new()),
+            shortcut_registrations: Mutex::new(Vec::new()),
+            last_transcript: Mutex::new(None),
+            clipboard_keeper: Mutex::new(ClipboardKeeper::default()),
+            text_sink: Box::new(|| Box::new(SystemTextSink::default())),
+            history: Mutex::new(None),
+            segments: Mutex::new(SegmentAggregator::default()),
+            partials: Mutex::new(PartialThrottle::default()),
+            saved_recording: Mutex::new(None),
+            replacer: Mutex::new(Replacer::default()),
+            sidecar_languages: Mutex::new(advertised_languages(&[])),
+            sidecar_models: Mutex::new(advertised_models(&[])),
+            backend: Mutex::new(None),
+            sidecar_events: Mutex::new(None),
+            sidecar_disabled: AtomicBool::new(false),
             recording_started: AtomicBool::new(false),
             suppress_disconnect_error: AtomicBool::new(false),
             shutdown: AtomicBool::new(false),
+            config_dir: None,
         }
     }
 }
 
-fn ensure_log_file(app: &AppHandle) -> Result<PathBuf, String> {
+fn ensure_log_file<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
     let dir = logs_dir(app)?;
     fs::create_dir_all(&dir).map_err(|e| format!("failed to create log dir: {e}"))?;
 
@@ -103,7 +348,7 @@ fn ensure_log_file(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(path)
 }
 
-fn log_line(app: &AppHandle, line: &str) {
+fn log_line<R: Runtime>(app: &AppHandle<R>, line: &str) {
     if let Ok(path) = ensure_log_file(app) {
         if let Ok(mut f) = OpenOptions::new().create(true).append(true).open(path) {
             let ts = Local::now().format("%Y-%m-%d %H:%M:%S");
@@ -112,21 +357,33 @@ fn log_line(app: &AppHandle, line: &str) {
     }
 }
 
-fn app_config_dir(app: &AppHandle) -> Result<PathBuf, String> {
-    let dir = app
-        .path()
-        .app_config_dir()
-        .map_err(|e| format!("failed to resolve app config dir: {e}"))?;
+fn app_config_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let configured = app
+        .try_state::<SharedState>()
+        .and_then(|shared| shared.config_dir.clone());
+    let dir = match configured {
+        Some(dir) => dir,
+        None => app
+            .path()
+            .app_config_dir()
+            .map_err(|e| format!("failed to resolve app config dir: {e}"))?,
+    };
     fs::create_dir_all(&dir).map_err(|e| format!("failed to create app config dir: {e}"))?;
     Ok(dir)
 }
 
-fn logs_dir(app: &AppHandle) -> Result<PathBuf, String> {
+fn logs_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
     let dir = app_config_dir(app)?.join("logs");
     fs::create_dir_all(&dir).map_err(|e| format!("failed to create logs dir: {e}"))?;
     Ok(dir)
 }
 
+fn recordings_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_config_dir(app)?.join(RECORDINGS_DIR_NAME);
+    fs::create_dir_all(&dir).map_err(|e| format!("failed to create recordings dir: {e}"))?;
+    Ok(dir)
+}
+
 fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(app_config_dir(app)?.join(SETTINGS_FILE_NAME))
 }
@@ -208,38 +465,348 @@ fn parse_shortcut(hotkey: &str) -> Result<Shortcut, String> {
         .map_err(|e| format!("invalid hotkey '{hotkey}': {e}"))
 }
 
-fn register_shortcut(app: &AppHandle, hotkey: &str) -> Result<(), String> {
-    let shortcut = parse_shortcut(hotkey)?;
+fn shortcut_bindings(settings: &AppSettings) -> Vec<ShortcutBinding> {
+    let mut bindings = vec![ShortcutBinding {
+        action: ShortcutAction::Dictate,
+        hotkey: current_hotkey(settings).to_string(),
+    }];
+    bindings.extend(settings.keymap.iter().cloned());
+    bindings
+}
+
+/// Registers every bound shortcut. A shortcut the OS refuses (e.g. taken by another app)
+/// is reported in its own entry and does not prevent the others from working.
+fn register_shortcuts(
+    app: &AppHandle,
+    settings: &AppSettings,
+) -> Result<Vec<ShortcutRegistration>, String> {
     let manager = app.global_shortcut();
     manager
         .unregister_all()
         .map_err(|e| format!("failed to unregister shortcuts: {e}"))?;
-    manager
-        .register(shortcut)
-        .map_err(|e| format!("failed to register shortcut: {e}"))?;
-    Ok(())
+
+    let mut actions = HashMap::new();
+    let mut registrations = Vec::new();
+    for binding in shortcut_bindings(settings) {
+        let hotkey = binding.hotkey.trim().to_string();
+        if hotkey.is_empty() {
+            continue;
+        }
+
+        let result = parse_shortcut(&hotkey).and_then(|shortcut| {
+            manager
+                .register(shortcut)
+                .map(|_| shortcut.id())
+                .map_err(|e| format!("failed to register shortcut: {e}"))
+        });
+        let error = match result {
+            Ok(id) => {
+                actions.insert(id, binding.action);
+                None
+            }
+            Err(e) => {
+                log_line(
+                    app,
+                    &format!("shortcut '{hotkey}' for {}: {e}", binding.action.label()),
+                );
+                Some(e)
+            }
+        };
+        registrations.push(ShortcutRegistration {
+            action: binding.action,
+            hotkey,
+            error,
+        });
+    }
+
+    let shared = app.state::<SharedState>();
+    if let Ok(mut guard) = shared.shortcut_actions.lock() {
+        *guard = actions;
+    }
+    if let Ok(mut guard) = shared.shortcut_registrations.lock() {
+        *guard = registrations.clone();
+    }
+    Ok(registrations)
+}
+
+fn emit_asr_event<R: Runtime>(app: &AppHandle<R>, event: &SidecarEvent) {
+    let _ = app.emit("asr_event", event);
+}
+
+fn emit_asr_error<R: Runtime>(app: &AppHandle<R>, message: impl Into<String>) {
+    emit_asr_event(app, &SidecarEvent::error(message));
+}
+
+fn current_output_mode<R: Runtime>(app: &AppHandle<R>) -> OutputMode {
+    let shared = app.state::<SharedState>();
+    let settings = shared.settings.lock();
+    settings.map(|s| s.output_mode).unwrap_or_default()
+}
+
+fn output_transcript<R: Runtime>(app: &AppHandle<R>, text: &str) {
+    let (mode, restore_clipboard, restore_after_sec) = {
+        let shared = app.state::<SharedState>();
+        let settings = shared.settings.lock();
+        settings
+            .map(|s| (s.output_mode, s.restore_clipboard, s.clipboard_restore_sec))
+            .unwrap_or_default()
+    };
+    let app = app.clone();
+    let text = text.to_string();
+    // Pasting waits for the target app before restoring the clipboard; keep that off the reader.
+    std::thread::spawn(move || {
+        let mut sink = (app.state::<SharedState>().text_sink)();
+        match deliver_text(sink.as_mut(), mode, &text, restore_clipboard) {
+            Ok(outcome) => {
+                log_line(&app, &format!("delivered transcript ({mode:?})"));
+                if let ClipboardOutcome::Replaced(previous) = outcome {
+                    let shared = app.state::<SharedState>();
+                    let generation = match shared.clipboard_keeper.lock() {
+                        Ok(mut keeper) => keeper.record(previous, &text),
+                        Err(_) => return,
+                    };
+                    if restore_after_sec > 0 {
+                        schedule_clipboard_restore(&app, generation, restore_after_sec);
+                    }
+                }
+            }
+            Err(e) => {
+                log_line(&app, &format!("transcript output failed: {e}"));
+                emit_asr_error(&app, format!("Transcript output failed: {e}"));
+            }
+        }
+    });
 }
 
-fn emit_asr_event(app: &AppHandle, payload: &Value) {
-    let _ = app.emit("asr_event", payload.clone());
+fn schedule_clipboard_restore<R: Runtime>(app: &AppHandle<R>, generation: u64, after_sec: u64) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_secs(after_sec));
+        let shared = app.state::<SharedState>();
+        let mut sink = (shared.text_sink)();
+        let current = sink.clipboard_snapshot();
+        let snapshot = match shared.clipboard_keeper.lock() {
+            Ok(mut keeper) => keeper.take_if_current(generation, current.as_ref()),
+            Err(_) => return,
+        };
+        if let Some(snapshot) = snapshot {
+            match sink.restore_clipboard(&snapshot) {
+                Ok(()) => log_line(&app, "restored previous clipboard contents"),
+                Err(e) => log_line(&app, &format!("clipboard restore failed: {e}")),
+            }
+        }
+    });
 }
 
-fn copy_text_to_clipboard(app: &AppHandle, text: &str) {
-    match Clipboard::new().and_then(|mut cb| cb.set_text(text.to_string())) {
-        Ok(_) => log_line(app, "copied transcript to clipboard"),
+fn restore_saved_clipboard(app: &AppHandle) {
+    let shared = app.state::<SharedState>();
+    let snapshot = match shared.clipboard_keeper.lock() {
+        Ok(mut keeper) => keeper.take(),
+        Err(_) => return,
+    };
+    let Some(snapshot) = snapshot else {
+        log_line(app, "no saved clipboard contents to restore");
+        return;
+    };
+
+    match (shared.text_sink)().restore_clipboard(&snapshot) {
+        Ok(()) => log_line(app, "restored previous clipboard contents"),
         Err(e) => {
-            log_line(app, &format!("clipboard copy failed: {e}"));
-            emit_asr_event(
+            log_line(app, &format!("clipboard restore failed: {e}"));
+            emit_asr_error(app, format!("Clipboard restore failed: {e}"));
+        }
+    }
+}
+
+fn with_history<T, R: Runtime>(
+    app: &AppHandle<R>,
+    f: impl FnOnce(&mut HistoryStore) -> Result<T, String>,
+) -> Result<T, String> {
+    let shared = app.state::<SharedState>();
+    let mut guard = shared
+        .history
+        .lock()
+        .map_err(|_| "failed to lock history mutex".to_string())?;
+    let store = guard
+        .as_mut()
+        .ok_or_else(|| "history is not available".to_string())?;
+    let result = f(store);
+    let dropped_audio = store.take_dropped_audio();
+    drop(guard);
+    remove_recordings(app, dropped_audio);
+    result
+}
+
+/// Deletes the recordings of history entries that are gone; missing files are skipped.
+fn remove_recordings<R: Runtime>(app: &AppHandle<R>, audio_paths: Vec<String>) {
+    for audio_path in audio_paths {
+        match fs::remove_file(&audio_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => log_line(
                 app,
-                &json!({
-                    "event": "error",
-                    "message": format!("Clipboard copy failed: {e}")
-                }),
-            );
+                &format!("failed to delete recording {audio_path}: {e}"),
+            ),
+        }
+    }
+}
+
+fn apply_recording_retention<R: Runtime>(app: &AppHandle<R>) {
+    let policy = {
+        let shared = app.state::<SharedState>();
+        let settings = match shared.settings.lock() {
+            Ok(guard) => guard,
+            Err(_) => return,
+        };
+        RetentionPolicy::from_limits(settings.audio_retention_mb, settings.audio_retention_days)
+    };
+    let Ok(dir) = app_config_dir(app).map(|dir| dir.join(RECORDINGS_DIR_NAME)) else {
+        return;
+    };
+    // The recording saved last is not linked to its history entry until the transcript arrives.
+    let keep = app
+        .state::<SharedState>()
+        .saved_recording
+        .lock()
+        .ok()
+        .and_then(|guard| guard.as_ref().map(|(_, path)| PathBuf::from(path)));
+    let linked: HashSet<PathBuf> = with_history(app, |h| {
+        Ok(h.list(None)
+            .into_iter()
+            .filter_map(|entry| entry.audio_path.map(PathBuf::from))
+            .collect())
+    })
+    .unwrap_or_default();
+
+    let now = std::time::SystemTime::now();
+    let is_linked = |path: &Path| linked.contains(path);
+    match apply_retention(&dir, policy, now, keep.as_deref(), is_linked) {
+        Ok(outcome) => {
+            for failure in &outcome.failed {
+                log_line(app, &format!("recording retention: {failure}"));
+            }
+            if !outcome.removed.is_empty() {
+                log_line(
+                    app,
+                    &format!(
+                        "recording retention removed {} file(s), {} of them linked to history",
+                        outcome.removed.len(),
+                        outcome.removed_linked
+                    ),
+                );
+            }
         }
+        Err(e) => log_line(app, &format!("recording retention failed: {e}")),
+    }
+}
+
+/// Deletes a stored recording unless another history entry still links to it.
+fn remove_unreferenced_recording<R: Runtime>(app: &AppHandle<R>, audio_path: &str) {
+    if with_history(app, |h| Ok(h.references_audio(audio_path))).unwrap_or(true) {
+        return;
+    }
+    if let Err(e) = fs::remove_file(audio_path) {
+        log_line(
+            app,
+            &format!("failed to delete recording {audio_path}: {e}"),
+        );
+    }
+}
+
+fn replacements_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(app)?.join(REPLACEMENTS_FILE_NAME))
+}
+
+fn load_replacements(app: &AppHandle) -> Result<(), String> {
+    let rules = load_rules(&replacements_path(app)?)?;
+    let replacer = Replacer::new(&rules)?;
+    let shared = app.state::<SharedState>();
+    let mut guard = shared
+        .replacer
+        .lock()
+        .map_err(|_| "failed to lock replacer mutex".to_string())?;
+    *guard = replacer;
+    Ok(())
+}
+
+fn sidecar_languages(shared: &SharedState) -> Vec<String> {
+    match shared.sidecar_languages.lock() {
+        Ok(languages) => languages.clone(),
+        Err(_) => advertised_languages(&[]),
     }
 }
 
+/// Turns raw model output into the text that is shown, copied, exported and kept in history, or
+/// `None` when a spoken command cancelled the dictation. Spoken commands run first, then
+/// normalization, so replacement rules see the cleaned-up text and have the last word.
+fn postprocess_transcript<R: Runtime>(app: &AppHandle<R>, text: &str) -> Option<String> {
+    let shared = app.state::<SharedState>();
+    let (language_mode, mut normalization, spoken_commands) = match shared.settings.lock() {
+        Ok(settings) => (
+            settings.language_mode.clone(),
+            settings.normalization,
+            settings.spoken_commands.clone(),
+        ),
+        Err(_) => Default::default(),
+    };
+    let language = resolve_language(&language_mode, &sidecar_languages(&shared));
+    let profile = LanguageProfile::for_language(&language);
+    normalization.spoken_numbers &= profile.spoken_numbers;
+    let text = if spoken_commands.enabled {
+        let defaults = if profile.default_commands {
+            default_commands()
+        } else {
+            Vec::new()
+        };
+        match SpokenCommands::new(&defaults, &spoken_commands) {
+            Ok(commands) => commands.apply(text)?,
+            Err(e) => {
+                log_line(app, &format!("spoken commands skipped: {e}"));
+                text.to_string()
+            }
+        }
+    } else {
+        text.to_string()
+    };
+    let normalized = Pipeline::new(&normalization).apply(&text);
+    let replaced = match shared.replacer.lock() {
+        Ok(replacer) => replacer.apply(&normalized),
+        Err(_) => normalized,
+    };
+    Some(replaced)
+}
+
+/// The clean-up for one timed segment: whitespace and replacement rules only. Spoken commands,
+/// numbers, capitalization and punctuation need the whole transcript, so they stay with it.
+fn postprocess_segment<R: Runtime>(app: &AppHandle<R>, text: &str) -> String {
+    let shared = app.state::<SharedState>();
+    let normalization = match shared.settings.lock() {
+        Ok(settings) => settings.normalization,
+        Err(_) => Default::default(),
+    };
+    let normalized = Pipeline::for_segments(&normalization).apply(text);
+    let replaced = match shared.replacer.lock() {
+        Ok(replacer) => replacer.apply(&normalized),
+        Err(_) => normalized,
+    };
+    replaced
+}
+
+fn open_history(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let path = app_config_dir(app)?.join(HISTORY_FILE_NAME);
+    let mut store = HistoryStore::open(path, settings.history_limit)?;
+    let dropped_audio = store.take_dropped_audio();
+    let shared = app.state::<SharedState>();
+    let mut guard = shared
+        .history
+        .lock()
+        .map_err(|_| "failed to lock history mutex".to_string())?;
+    *guard = Some(store);
+    drop(guard);
+    remove_recordings(app, dropped_audio);
+    Ok(())
+}
+
 fn find_python_script(app: &AppHandle) -> Result<PathBuf, String> {
     let mut checked: Vec<PathBuf> = Vec::new();
     let mut candidates: Vec<PathBuf> = vec![
@@ -451,7 +1018,11 @@ fn spawn_sidecar_command(
     spawn_stderr_reader(app.clone(), stderr);
 
     log_line(app, &format!("started sidecar with '{label}'"));
-    Ok(SidecarProcess { child, stdin })
+    Ok(SidecarProcess {
+        child,
+        stdin,
+        started_at: Instant::now(),
+    })
 }
 
 fn hide_settings_window_inner(app: &AppHandle) -> Result<(), String> {
@@ -511,6 +1082,7 @@ fn start_sidecar_process(app: &AppHandle) -> Result<SidecarProcess, String> {
     );
     let script = find_python_script(app)?;
 
+    #[cfg_attr(not(target_os = "windows"), allow(unused_mut))]
     let mut attempts: Vec<(String, Vec<String>)> = vec![
         (
             "python".to_string(),
@@ -585,33 +1157,19 @@ fn spawn_stdout_reader(app: AppHandle, stdout: ChildStdout) {
                         }
                     };
 
-                    match serde_json::from_str::<Value>(&raw) {
-                        Ok(payload) => {
-                            if payload.get("event")
-                                == Some(&Value::String("sidecar_idle_restart".to_string()))
-                            {
-                                let shared = app.state::<SharedState>();
-                                shared
-                                    .suppress_disconnect_error
-                                    .store(true, Ordering::SeqCst);
-                                log_line(&app, "sidecar requested idle restart");
-                                continue;
-                            }
-
-                            if payload.get("event") == Some(&Value::String("final_transcript".to_string())) {
-                                if let Some(text) = payload.get("text").and_then(Value::as_str) {
-                                    copy_text_to_clipboard(&app, text);
-                                }
-                            }
-
-                            if payload.get("event") == Some(&Value::String("ready".to_string())) {
-                                log_line(&app, "sidecar ready event received");
-                            }
-
-                            emit_asr_event(&app, &payload);
+                    match parse_event(&raw) {
+                        // Meter updates arrive ~20 times a second: straight to the popup, unlogged.
+                        Ok(SidecarMessage {
+                            event: event @ SidecarEvent::AudioLevel { .. },
+                            ..
+                        }) => emit_asr_event(&app, &event),
+                        Ok(message) => handle_sidecar_message(&app, message),
+                        Err(ProtocolError::InvalidJson(e)) => {
+                            log_line(&app, &format!("invalid sidecar JSON '{raw}': {e}"));
                         }
                         Err(e) => {
-                            log_line(&app, &format!("invalid sidecar JSON '{raw}': {e}"));
+                            log_line(&app, &format!("sidecar protocol error in '{raw}': {e}"));
+                            emit_asr_error(&app, format!("ASR sidecar protocol error: {e}"));
                         }
                     }
                 }
@@ -624,31 +1182,313 @@ fn spawn_stdout_reader(app: AppHandle, stdout: ChildStdout) {
 
         let shared = app.state::<SharedState>();
         shared.recording_started.store(false, Ordering::SeqCst);
+        shared.requests.clear();
+        set_model_loading(&app, false);
         let suppress_disconnect = shared
             .suppress_disconnect_error
             .swap(false, Ordering::SeqCst);
         let shutting_down = shared.shutdown.load(Ordering::SeqCst);
 
         if !shutting_down && !suppress_disconnect {
-            emit_asr_event(
-                &app,
-                &json!({
-                    "event": "error",
-                    "message": "ASR sidecar disconnected. It will restart on next action."
-                }),
-            );
+            emit_asr_error(&app, "ASR sidecar disconnected. Restarting it.");
         }
+        wake_sidecar_supervisor(&shared);
     });
 }
 
+fn handle_sidecar_message(app: &AppHandle, message: SidecarMessage) {
+    let event = match message.id {
+        Some(id) => {
+            let shared = app.state::<SharedState>();
+            match shared.requests.resolve(id, message.event) {
+                Some(event) => event,
+                // Delivered to a caller awaiting this request.
+                None => return,
+            }
+        }
+        None => message.event,
+    };
+
+    let sink = app
+        .state::<SharedState>()
+        .sidecar_events
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone());
+    match sink {
+        Some(sink) => sink(message.id, event),
+        None => handle_sidecar_event(app, message.id, event),
+    }
+}
+
+/// Sends a partial transcript to the popup, at most once per `PARTIAL_MIN_INTERVAL`.
+fn emit_partial_throttled<R: Runtime>(app: &AppHandle<R>, text: String) {
+    let shared = app.state::<SharedState>();
+    let decision = match shared.partials.lock() {
+        Ok(mut throttle) => throttle.offer(text, Instant::now()),
+        Err(_) => return,
+    };
+
+    match decision {
+        PartialDecision::Emit(text) => {
+            emit_asr_event(app, &SidecarEvent::PartialTranscript { text });
+        }
+        PartialDecision::FlushAfter { delay, generation } => {
+            let app = app.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(delay);
+                let shared = app.state::<SharedState>();
+                // Emit under the lock: `reset_partials` runs before the final transcript is
+                // emitted, so a late flush either goes out first or finds nothing to send.
+                let Ok(mut throttle) = shared.partials.lock() else {
+                    return;
+                };
+                if let Some(text) = throttle.flush(generation, Instant::now()) {
+                    emit_asr_event(&app, &SidecarEvent::PartialTranscript { text });
+                }
+            });
+        }
+        PartialDecision::Wait => {}
+    }
+}
+
+fn reset_partials<R: Runtime>(app: &AppHandle<R>) {
+    let shared = app.state::<SharedState>();
+    if let Ok(mut throttle) = shared.partials.lock() {
+        throttle.reset();
+    };
+}
+
+fn handle_sidecar_event<R: Runtime>(
+    app: &AppHandle<R>,
+    request_id: Option<u64>,
+    event: SidecarEvent,
+) {
+    let event = match event {
+        SidecarEvent::FinalTranscript { text } => match postprocess_transcript(app, &text) {
+            Some(text) => SidecarEvent::FinalTranscript { text },
+            None => {
+                log_line(app, "dictation cancelled by spoken command");
+                let shared = app.state::<SharedState>();
+                let saved_recording = shared
+                    .saved_recording
+                    .lock()
+                    .ok()
+                    .and_then(|mut guard| guard.take_if(|(id, _)| *id == request_id));
+                if let Some((_, path)) = saved_recording {
+                    remove_unreferenced_recording(app, &path);
+                }
+                SidecarEvent::JobCancelled
+            }
+        },
+        // Segments get the clean-up that is safe per piece, so replacements show in exports too.
+        SidecarEvent::TranscriptSegment {
+            index,
+            start_ms,
+            end_ms,
+            text,
+        } => SidecarEvent::TranscriptSegment {
+            index,
+            start_ms,
+            end_ms,
+            text: postprocess_segment(app, &text),
+        },
+        other => other,
+    };
+
+    if matches!(
+        event,
+        SidecarEvent::RecordingStarted
+            | SidecarEvent::FinalTranscript { .. }
+            | SidecarEvent::JobCancelled
+            | SidecarEvent::NoSpeech
+            | SidecarEvent::Error { .. }
+    ) {
+        reset_partials(app);
+    }
+
+    match &event {
+        SidecarEvent::SidecarIdleRestart => {
+            let shared = app.state::<SharedState>();
+            shared
+                .suppress_disconnect_error
+                .store(true, Ordering::SeqCst);
+            shared.idle_released.store(true, Ordering::SeqCst);
+            log_line(app, "sidecar requested idle restart");
+            return;
+        }
+        SidecarEvent::FinalTranscript { text } => {
+            let shared = app.state::<SharedState>();
+            if let Ok(mut guard) = shared.last_transcript.lock() {
+                *guard = Some(text.clone());
+            };
+            // Sidecars without segment events leave no timing, rather than the previous job's.
+            if let Ok(mut aggregator) = shared.segments.lock() {
+                if !aggregator.is_for(request_id) {
+                    aggregator.reset(request_id);
+                }
+            };
+            let timestamp_ms = Local::now().timestamp_millis();
+            let saved_recording = shared
+                .saved_recording
+                .lock()
+                .ok()
+                .and_then(|mut guard| guard.take_if(|(id, _)| *id == request_id))
+                .map(|(_, path)| path);
+            if let Err(e) = with_history(app, |h| {
+                h.begin(request_id, text, timestamp_ms)?;
+                if let Some(path) = &saved_recording {
+                    h.attach_audio(request_id, path);
+                }
+                Ok(())
+            }) {
+                log_line(app, &format!("history write failed: {e}"));
+            }
+            output_transcript(app, text);
+        }
+        SidecarEvent::Metrics {
+            latency_ms,
+            device,
+            model,
+        } => {
+            if let Err(e) = with_history(app, |h| {
+                h.attach_metrics(request_id, *latency_ms, device, model)
+            }) {
+                log_line(app, &format!("history write failed: {e}"));
+            }
+        }
+        SidecarEvent::TranscriptSegment {
+            index,
+            start_ms,
+            end_ms,
+            text,
+        } => {
+            let assembled = {
+                let shared = app.state::<SharedState>();
+                let mut aggregator = match shared.segments.lock() {
+                    Ok(guard) => guard,
+                    Err(_) => return,
+                };
+                aggregator.push(
+                    request_id,
+                    TranscriptSegment {
+                        index: *index,
+                        start_ms: *start_ms,
+                        end_ms: *end_ms,
+                        text: text.clone(),
+                    },
+                );
+                aggregator.text()
+            };
+            emit_asr_event(app, &event);
+            emit_partial_throttled(app, assembled);
+            return;
+        }
+        SidecarEvent::PartialTranscript { text } => {
+            emit_partial_throttled(app, text.clone());
+            return;
+        }
+        // The sidecar stopped on its own: the next hotkey press must start a new recording.
+        SidecarEvent::AutoStopped { silence_ms } => {
+            let shared = app.state::<SharedState>();
+            shared.recording_started.store(false, Ordering::SeqCst);
+            log_line(
+                app,
+                &format!("recording auto-stopped after {silence_ms} ms of silence"),
+            );
+            show_popup(app);
+        }
+        SidecarEvent::RecordingSaved { path, duration_ms } => {
+            log_line(
+                app,
+                &format!("recording saved to {path} ({duration_ms} ms)"),
+            );
+            let shared = app.state::<SharedState>();
+            if let Ok(mut guard) = shared.saved_recording.lock() {
+                *guard = Some((request_id, path.clone()));
+            };
+            apply_recording_retention(app);
+            return;
+        }
+        SidecarEvent::InputDeviceFallback { requested, message } => {
+            log_line(
+                app,
+                &format!("input device '{requested}' unavailable: {message}"),
+            );
+        }
+        // Not an error: the popup shows a gentle hint instead of the error state.
+        SidecarEvent::NoSpeech => log_line(app, "no speech detected; transcription skipped"),
+        // A failed model load is reported as a plain error, so clear the loading flag too.
+        SidecarEvent::Error { .. } => set_model_loading(app, false),
+        SidecarEvent::Ready {
+            device,
+            model,
+            protocol_version,
+            sidecar_version,
+            commands,
+            languages,
+            models,
+        } => {
+            log_line(
+                app,
+                &format!(
+                    "sidecar ready: version='{sidecar_version}' protocol=v{protocol_version} model={model} device={device}"
+                ),
+            );
+            let compat = SidecarCompatibility::evaluate(*protocol_version, commands);
+            match &compat {
+                SidecarCompatibility::Degraded { missing } => log_line(
+                    app,
+                    &format!("sidecar lacks commands {missing:?}; running degraded"),
+                ),
+                SidecarCompatibility::Incompatible { reason } => {
+                    log_line(app, &format!("sidecar rejected: {reason}"));
+                    emit_asr_error(app, reason.clone());
+                }
+                _ => {}
+            }
+            let shared = app.state::<SharedState>();
+            if let Ok(mut guard) = shared.sidecar_compat.lock() {
+                *guard = compat;
+            };
+            if let Ok(mut guard) = shared.sidecar_languages.lock() {
+                *guard = advertised_languages(languages);
+            };
+            if let Ok(mut guard) = shared.sidecar_models.lock() {
+                *guard = advertised_models(models);
+            };
+            refresh_status_from_compat(app, &shared);
+            preload_model_if_enabled(app);
+        }
+        SidecarEvent::ModelLoading { model } => {
+            log_line(app, &format!("sidecar loading model {model}"));
+            set_model_loading(app, true);
+            return;
+        }
+        SidecarEvent::ModelLoaded {
+            model,
+            device,
+            load_ms,
+        } => {
+            log_line(
+                app,
+                &format!("sidecar loaded model {model} on {device} in {load_ms}ms"),
+            );
+            set_model_loading(app, false);
+            return;
+        }
+        _ => {}
+    }
+
+    emit_asr_event(app, &event);
+}
+
 fn spawn_stderr_reader(app: AppHandle, stderr: ChildStderr) {
     std::thread::spawn(move || {
         let reader = BufReader::new(stderr);
-        for line in reader.lines() {
-            if let Ok(raw) = line {
-                if !raw.trim().is_empty() {
-                    log_line(&app, &format!("sidecar stderr: {raw}"));
-                }
+        for raw in reader.lines().map_while(Result::ok) {
+            if !raw.trim().is_empty() {
+                log_line(&app, &format!("sidecar stderr: {raw}"));
             }
         }
     });
@@ -677,40 +1517,433 @@ fn ensure_sidecar_running(app: &AppHandle, shared: &SharedState) -> Result<(), S
     };
 
     if needs_restart {
-        *guard = Some(start_sidecar_process(app)?);
+        update_sidecar_status(app, |report| {
+            report.status = SidecarStatus::Starting;
+            report.detail = None;
+            report.model_loading = false;
+        });
+        let mut proc = match start_sidecar_process(app) {
+            Ok(proc) => proc,
+            Err(e) => {
+                set_sidecar_status(app, SidecarStatus::Failed, Some(e.clone()));
+                return Err(e);
+            }
+        };
+        shared.idle_released.store(false, Ordering::SeqCst);
+        if let Ok(mut compat) = shared.sidecar_compat.lock() {
+            *compat = SidecarCompatibility::Unknown;
+        }
+
+        // Every fresh process gets the handshake and current config, including lazy restarts.
+        let config = shared
+            .settings
+            .lock()
+            .map(|settings| sidecar_config(app, &settings))
+            .map_err(|_| "failed to lock settings mutex".to_string())?;
+        for command in [SidecarCommand::init(), SidecarCommand::SetConfig { config }] {
+            write_sidecar_command(&mut proc, shared.requests.next_id(), &command)?;
+        }
+        *guard = Some(proc);
     }
 
     Ok(())
 }
 
-fn send_sidecar_command(app: &AppHandle, command: Value) -> Result<(), String> {
-    let shared = app.state::<SharedState>();
-    ensure_sidecar_running(app, &shared)?;
+/// Records a new supervisor status and mirrors it to the tray and frontend.
+/// Returns `false` when the status did not change.
+fn set_sidecar_status<R: Runtime>(
+    app: &AppHandle<R>,
+    status: SidecarStatus,
+    detail: Option<String>,
+) -> bool {
+    update_sidecar_status(app, |report| {
+        report.status = status;
+        report.detail = detail;
+    })
+}
 
-    let mut guard = shared
-        .sidecar
-        .lock()
-        .map_err(|_| "failed to lock sidecar mutex".to_string())?;
+fn set_model_loading<R: Runtime>(app: &AppHandle<R>, loading: bool) {
+    update_sidecar_status(app, |report| report.model_loading = loading);
+}
 
-    let proc = guard
-        .as_mut()
-        .ok_or_else(|| "sidecar is not available".to_string())?;
+fn update_sidecar_status<R: Runtime>(
+    app: &AppHandle<R>,
+    change: impl FnOnce(&mut SidecarStatusReport),
+) -> bool {
+    let shared = app.state::<SharedState>();
+    let report = {
+        let Ok(mut guard) = shared.sidecar_status.lock() else {
+            return false;
+        };
+        let mut next = guard.clone();
+        change(&mut next);
+        if *guard == next {
+            return false;
+        }
+        *guard = next.clone();
+        next
+    };
 
-    let line = format!("{}\n", command);
-    proc.stdin
-        .write_all(line.as_bytes())
-        .map_err(|e| format!("failed to write sidecar command: {e}"))?;
-    proc.stdin
-        .flush()
-        .map_err(|e| format!("failed to flush sidecar command: {e}"))?;
+    let mut line = match &report.detail {
+        Some(detail) => format!("{}: {detail}", report.status.label()),
+        None => report.status.label().to_string(),
+    };
+    let loading = if report.model_loading {
+        " (loading model...)"
+    } else {
+        ""
+    };
+    line.push_str(loading);
+    log_line(app, &format!("sidecar status: {line}"));
 
-    Ok(())
+    if let Ok(guard) = shared.tray_status_item.lock() {
+        if let Some(item) = guard.as_ref() {
+            let _ = item.set_text(format!("ASR: {}{loading}", report.status.label()));
+        }
+    }
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(format!("Sber Whisper - {line}")));
+    }
+    let _ = app.emit("sidecar_status", &report);
+    true
 }
 
-fn popup_window<R: Runtime>(app: &AppHandle<R>) -> Result<WebviewWindow<R>, String> {
-    app.get_webview_window("popup")
-        .ok_or_else(|| "popup window not found".to_string())
-}
+fn refresh_status_from_compat<R: Runtime>(app: &AppHandle<R>, shared: &SharedState) {
+    let compat = match shared.sidecar_compat.lock() {
+        Ok(guard) => guard.clone(),
+        Err(_) => return,
+    };
+    match compat {
+        SidecarCompatibility::Unknown => {}
+        SidecarCompatibility::Compatible => {
+            set_sidecar_status(app, SidecarStatus::Ready, None);
+        }
+        SidecarCompatibility::Degraded { missing } => {
+            set_sidecar_status(
+                app,
+                SidecarStatus::Degraded,
+                Some(format!("unsupported commands: {}", missing.join(", "))),
+            );
+        }
+        SidecarCompatibility::Incompatible { reason } => {
+            set_sidecar_status(app, SidecarStatus::Failed, Some(reason));
+        }
+    }
+}
+
+fn wake_sidecar_supervisor(shared: &SharedState) {
+    if let Ok(guard) = shared.supervisor_wake.lock() {
+        if let Some(tx) = guard.as_ref() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+fn probe_sidecar(app: &AppHandle, shared: &SharedState) -> SidecarProbe {
+    if let Ok(mut guard) = shared.sidecar.lock() {
+        let exited = match guard.as_mut() {
+            Some(proc) => match proc.child.try_wait() {
+                Ok(None) => {
+                    return SidecarProbe::Running {
+                        uptime: proc.started_at.elapsed(),
+                    }
+                }
+                Ok(Some(status)) => {
+                    log_line(
+                        app,
+                        &format!("supervisor: sidecar exited with status {status}"),
+                    );
+                    true
+                }
+                Err(e) => {
+                    log_line(app, &format!("supervisor: sidecar try_wait failed: {e}"));
+                    true
+                }
+            },
+            None => false,
+        };
+        if exited {
+            *guard = None;
+        }
+    }
+
+    if shared.idle_released.load(Ordering::SeqCst) {
+        SidecarProbe::IdleReleased
+    } else {
+        SidecarProbe::Exited
+    }
+}
+
+/// Healthcheck written straight to the running process, without the lazy restart
+/// that `send_sidecar_command` performs.
+fn ping_sidecar(shared: &SharedState) -> Result<(), String> {
+    let id = shared.requests.next_id();
+    let reply = shared.requests.register(id);
+
+    let written = match shared.sidecar.lock() {
+        Ok(mut guard) => match guard.as_mut() {
+            Some(proc) => write_sidecar_command(proc, id, &SidecarCommand::Healthcheck),
+            None => Err("sidecar is not running".to_string()),
+        },
+        Err(_) => Err("failed to lock sidecar mutex".to_string()),
+    };
+    if let Err(e) = written {
+        shared.requests.forget(id);
+        return Err(e);
+    }
+
+    // Any reply proves the stdin loop is alive, even an error from a degraded sidecar.
+    reply
+        .recv_timeout(SIDECAR_PING_TIMEOUT)
+        .map(|_| ())
+        .map_err(|_| {
+            shared.requests.forget(id);
+            format!(
+                "no healthcheck reply within {}s",
+                SIDECAR_PING_TIMEOUT.as_secs()
+            )
+        })
+}
+
+fn kill_sidecar(shared: &SharedState) {
+    let taken = match shared.sidecar.lock() {
+        Ok(mut guard) => guard.take(),
+        Err(_) => None,
+    };
+    if let Some(mut proc) = taken {
+        let _ = proc.child.kill();
+        let _ = proc.child.wait();
+    }
+}
+
+fn spawn_sidecar_supervisor(app: AppHandle) {
+    let (wake_tx, wake_rx) = mpsc::channel::<()>();
+    if let Ok(mut guard) = app.state::<SharedState>().supervisor_wake.lock() {
+        *guard = Some(wake_tx);
+    }
+
+    std::thread::spawn(move || {
+        let mut backoff = RestartBackoff::default();
+        let mut missed_pings: u32 = 0;
+
+        loop {
+            let _ = wake_rx.recv_timeout(SIDECAR_WATCHDOG_INTERVAL);
+            let shared = app.state::<SharedState>();
+            if shared.shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+
+            match probe_sidecar(&app, &shared) {
+                SidecarProbe::Running { uptime } => {
+                    if uptime >= SIDECAR_STABLE_UPTIME {
+                        backoff.reset();
+                    }
+
+                    // Pings only make sense once the handshake accepted this sidecar.
+                    let handshake_done = matches!(
+                        shared.sidecar_compat.lock().as_deref(),
+                        Ok(SidecarCompatibility::Compatible
+                            | SidecarCompatibility::Degraded { .. })
+                    );
+                    if !handshake_done {
+                        continue;
+                    }
+
+                    match ping_sidecar(&shared) {
+                        Ok(()) => {
+                            if missed_pings > 0 {
+                                missed_pings = 0;
+                                refresh_status_from_compat(&app, &shared);
+                            }
+                            continue;
+                        }
+                        Err(e) => {
+                            missed_pings += 1;
+                            log_line(&app, &format!("supervisor: sidecar ping failed: {e}"));
+                            if missed_pings < SIDECAR_MAX_MISSED_PINGS {
+                                set_sidecar_status(
+                                    &app,
+                                    SidecarStatus::Degraded,
+                                    Some("ASR sidecar is not responding".to_string()),
+                                );
+                                continue;
+                            }
+                            log_line(&app, "supervisor: sidecar hung, killing it");
+                            missed_pings = 0;
+                            kill_sidecar(&shared);
+                        }
+                    }
+                }
+                SidecarProbe::IdleReleased => {
+                    backoff.reset();
+                    // With preload on, respawn now; the `ready` handler then warms the model.
+                    if preload_enabled(&shared) {
+                        log_line(&app, "supervisor: respawning idle sidecar to preload model");
+                        if let Err(e) = ensure_sidecar_running(&app, &shared) {
+                            log_line(&app, &format!("supervisor: sidecar respawn failed: {e}"));
+                        }
+                    }
+                    continue;
+                }
+                SidecarProbe::Exited => {}
+            }
+
+            let Some(delay) = backoff.next_delay() else {
+                let detail = format!(
+                    "ASR sidecar crashed {SIDECAR_MAX_RESTARTS} times in a row; it will retry on next hotkey press"
+                );
+                if set_sidecar_status(&app, SidecarStatus::Failed, Some(detail.clone())) {
+                    emit_asr_error(&app, detail);
+                }
+                continue;
+            };
+
+            log_line(
+                &app,
+                &format!("supervisor: restarting sidecar in {}ms", delay.as_millis()),
+            );
+            std::thread::sleep(delay);
+            if shared.shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+            if let Err(e) = ensure_sidecar_running(&app, &shared) {
+                log_line(&app, &format!("supervisor: sidecar restart failed: {e}"));
+            }
+        }
+    });
+}
+
+fn preload_enabled(shared: &SharedState) -> bool {
+    shared
+        .settings
+        .lock()
+        .map(|settings| settings.preload_model)
+        .unwrap_or(false)
+}
+
+/// Asks the sidecar to warm the model when the setting is on. Failures only get logged:
+/// the model still loads lazily on the first dictation.
+fn preload_model_if_enabled<R: Runtime>(app: &AppHandle<R>) {
+    let shared = app.state::<SharedState>();
+    if !preload_enabled(&shared) {
+        return;
+    }
+    let id = shared.requests.next_id();
+    if let Err(e) = asr_backend(app).and_then(|backend| backend.preload_model(id)) {
+        log_line(app, &format!("model preload skipped: {e}"));
+    }
+}
+
+/// Loads a newly chosen model right away, so a broken choice fails now instead of on the next
+/// dictation. A running job finishes on the old model; the sidecar swaps before the next one.
+fn reload_model(app: &AppHandle, previous: &str, model: &str) {
+    log_line(
+        app,
+        &format!("model changed from {previous} to {model}; reloading"),
+    );
+    let id = app.state::<SharedState>().requests.next_id();
+    if let Err(e) = asr_backend(app).and_then(|backend| backend.preload_model(id)) {
+        log_line(app, &format!("model reload skipped: {e}"));
+    }
+}
+
+fn validate_model(model: &str, shared: &SharedState) -> Result<(), String> {
+    if model.trim().is_empty() {
+        return Err("model must not be empty".to_string());
+    }
+    let known = shared
+        .sidecar_models
+        .lock()
+        .map_err(|_| "failed to lock models mutex".to_string())?;
+    if known.iter().any(|name| name == model) {
+        return Ok(());
+    }
+    Err(format!(
+        "model '{model}' is not available; choose one of: {}",
+        known.join(", ")
+    ))
+}
+
+fn write_sidecar_command(
+    proc: &mut SidecarProcess,
+    id: u64,
+    command: &SidecarCommand,
+) -> Result<(), String> {
+    let line = command.to_line(id)?;
+    proc.stdin
+        .write_all(line.as_bytes())
+        .map_err(|e| format!("failed to write sidecar command: {e}"))?;
+    proc.stdin
+        .flush()
+        .map_err(|e| format!("failed to flush sidecar command: {e}"))?;
+    Ok(())
+}
+
+/// Sends `command` and blocks until the sidecar answers it or `timeout` expires.
+fn request_sidecar(
+    app: &AppHandle,
+    command: &SidecarCommand,
+    timeout: Duration,
+) -> Result<SidecarEvent, String> {
+    let shared = app.state::<SharedState>();
+    let id = shared.requests.next_id();
+    // Register before writing so a fast reply cannot slip past the table.
+    let reply = shared.requests.register(id);
+
+    if let Err(e) = send_sidecar_command(app, id, command) {
+        shared.requests.forget(id);
+        return Err(e);
+    }
+
+    match reply.recv_timeout(timeout) {
+        Ok(event) => Ok(event),
+        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+            shared.requests.forget(id);
+            Err(format!(
+                "ASR sidecar did not answer '{}' within {}s",
+                command.name(),
+                timeout.as_secs()
+            ))
+        }
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => Err(format!(
+            "ASR sidecar disconnected before answering '{}'",
+            command.name()
+        )),
+    }
+}
+
+fn send_sidecar_command(app: &AppHandle, id: u64, command: &SidecarCommand) -> Result<(), String> {
+    let shared = app.state::<SharedState>();
+    if shared.sidecar_disabled.load(Ordering::SeqCst) {
+        return Err(format!(
+            "'{}' needs the ASR sidecar, which the current backend replaces",
+            command.name()
+        ));
+    }
+    ensure_sidecar_running(app, &shared)?;
+
+    shared
+        .sidecar_compat
+        .lock()
+        .map_err(|_| "failed to lock sidecar compat mutex".to_string())?
+        .check_command(command)?;
+
+    let mut guard = shared
+        .sidecar
+        .lock()
+        .map_err(|_| "failed to lock sidecar mutex".to_string())?;
+
+    let proc = guard
+        .as_mut()
+        .ok_or_else(|| "sidecar is not available".to_string())?;
+
+    write_sidecar_command(proc, id, command)
+}
+
+fn popup_window<R: Runtime>(app: &AppHandle<R>) -> Result<WebviewWindow<R>, String> {
+    app.get_webview_window("popup")
+        .ok_or_else(|| "popup window not found".to_string())
+}
 
 fn settings_window<R: Runtime>(app: &AppHandle<R>) -> Result<WebviewWindow<R>, String> {
     app.get_webview_window("settings")
@@ -743,45 +1976,282 @@ fn position_popup<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
     Ok(())
 }
 
-fn show_popup(app: &AppHandle) {
+fn show_popup<R: Runtime>(app: &AppHandle<R>) {
     if let Ok(popup) = popup_window(app) {
         if let Err(e) = position_popup(app) {
             log_line(app, &format!("popup positioning error: {e}"));
         }
 
         let _ = popup.show();
-        let _ = popup.set_focus();
+        // Pasting and typing go to the focused window, so the popup must not take focus.
+        if current_output_mode(app) == OutputMode::Clipboard {
+            let _ = popup.set_focus();
+        }
     }
 }
 
-fn hide_popup_inner(app: &AppHandle) -> Result<(), String> {
+fn hide_popup_inner<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
     let popup = popup_window(app)?;
     popup.hide().map_err(|e| format!("failed to hide popup: {e}"))?;
     Ok(())
 }
 
-fn send_command_or_emit_error(app: &AppHandle, payload: Value) {
-    if let Err(err) = send_sidecar_command(app, payload) {
-        log_line(app, &format!("sidecar command failed: {err}"));
-        emit_asr_event(app, &json!({ "event": "error", "message": err }));
+/// The stdio Python sidecar as an `AsrBackend`.
+struct SidecarBackend {
+    app: AppHandle,
+}
+
+impl AsrBackend for SidecarBackend {
+    fn start(&self, id: u64) -> Result<(), String> {
+        send_sidecar_command(&self.app, id, &SidecarCommand::StartRecording)
+    }
+
+    fn stop(&self, id: u64) -> Result<(), String> {
+        send_sidecar_command(&self.app, id, &SidecarCommand::StopAndTranscribe)
+    }
+
+    fn transcribe_file(&self, id: u64, path: &Path) -> Result<(), String> {
+        send_sidecar_command(
+            &self.app,
+            id,
+            &SidecarCommand::TranscribeFile {
+                path: path.to_string_lossy().into_owned(),
+            },
+        )
+    }
+
+    fn cancel(&self, id: u64) -> Result<(), String> {
+        send_sidecar_command(&self.app, id, &SidecarCommand::CancelCurrent)
+    }
+
+    fn set_config(&self, id: u64, config: &SidecarConfig) -> Result<(), String> {
+        send_sidecar_command(
+            &self.app,
+            id,
+            &SidecarCommand::SetConfig {
+                config: config.clone(),
+            },
+        )
+    }
+
+    fn preload_model(&self, id: u64) -> Result<(), String> {
+        send_sidecar_command(&self.app, id, &SidecarCommand::PreloadModel)
+    }
+
+    fn healthcheck(&self) -> Result<SidecarEvent, String> {
+        request_sidecar(&self.app, &SidecarCommand::Healthcheck, HEALTHCHECK_TIMEOUT)
+    }
+
+    fn list_models(&self) -> Result<SidecarEvent, String> {
+        request_sidecar(&self.app, &SidecarCommand::ListModels, MODEL_LIST_TIMEOUT)
+    }
+
+    fn list_input_devices(&self) -> Result<SidecarEvent, String> {
+        request_sidecar(
+            &self.app,
+            &SidecarCommand::ListInputDevices,
+            DEVICE_LIST_TIMEOUT,
+        )
+    }
+
+    fn set_events(&self, sink: EventSink) {
+        if let Ok(mut guard) = self.app.state::<SharedState>().sidecar_events.lock() {
+            *guard = Some(sink);
+        };
+    }
+}
+
+fn asr_backend<R: Runtime>(app: &AppHandle<R>) -> Result<Arc<dyn AsrBackend>, String> {
+    app.state::<SharedState>()
+        .backend
+        .lock()
+        .map_err(|_| "failed to lock backend mutex".to_string())?
+        .clone()
+        .ok_or_else(|| "ASR backend is not ready yet".to_string())
+}
+
+/// Runs `call` with a fresh request id for the command it sends.
+fn backend_call_or_emit_error<R: Runtime>(
+    app: &AppHandle<R>,
+    call: impl FnOnce(&dyn AsrBackend, u64) -> Result<(), String>,
+) {
+    let id = app.state::<SharedState>().requests.next_id();
+    if let Err(err) = asr_backend(app).and_then(|backend| call(backend.as_ref(), id)) {
+        log_line(app, &format!("ASR backend command failed: {err}"));
+        emit_asr_error(app, err);
     }
 }
 
-fn send_config_to_sidecar(app: &AppHandle, settings: &AppSettings) {
-    send_command_or_emit_error(
-        app,
-        json!({
-            "command": "set_config",
-            "config": {
-                "language_mode": settings.language_mode.clone(),
-                "popup_timeout_sec": settings.popup_timeout_sec,
-                "model_keepalive_min": settings.model_keepalive_min
+/// Installs the backend chosen by `SBER_WHISPER_ASR_BACKEND` and routes its events into the app.
+fn init_backend(app: &AppHandle) {
+    let kind = BackendKind::from_env();
+    let backend: Arc<dyn AsrBackend> = match kind {
+        BackendKind::Sidecar => Arc::new(SidecarBackend { app: app.clone() }),
+        BackendKind::Mock => Arc::new(MockBackend::from_env()),
+    };
+    install_backend(app, backend);
+
+    if kind == BackendKind::Mock {
+        let shared = app.state::<SharedState>();
+        shared.sidecar_disabled.store(true, Ordering::SeqCst);
+        set_sidecar_status(
+            app,
+            SidecarStatus::Ready,
+            Some("mock ASR backend".to_string()),
+        );
+        log_line(app, "using mock ASR backend; the sidecar is not started");
+        // The sidecar gets its config during the handshake; other backends get it here.
+        let settings = shared
+            .settings
+            .lock()
+            .map(|s| s.clone())
+            .unwrap_or_default();
+        send_config(app, &settings);
+    }
+}
+
+fn install_backend<R: Runtime>(app: &AppHandle<R>, backend: Arc<dyn AsrBackend>) {
+    let events_app = app.clone();
+    backend.set_events(Arc::new(move |request_id, event| {
+        handle_sidecar_event(&events_app, request_id, event)
+    }));
+    if let Ok(mut guard) = app.state::<SharedState>().backend.lock() {
+        *guard = Some(backend);
+    };
+}
+
+fn sidecar_config(app: &AppHandle, settings: &AppSettings) -> SidecarConfig {
+    let recordings_dir = if settings.keep_audio {
+        match recordings_dir(app) {
+            Ok(dir) => Some(dir.to_string_lossy().into_owned()),
+            Err(e) => {
+                log_line(app, &format!("recordings will not be kept: {e}"));
+                None
             }
-        }),
+        }
+    } else {
+        None
+    };
+
+    SidecarConfig {
+        language_mode: settings.language_mode.clone(),
+        popup_timeout_sec: settings.popup_timeout_sec,
+        model_keepalive_min: settings.model_keepalive_min,
+        streaming_partials: settings.streaming_partials,
+        vad_silence_ms: settings.vad.sidecar_silence_ms(),
+        vad_threshold_dbfs: settings.vad.threshold_dbfs,
+        input_device: settings
+            .input_device
+            .clone()
+            .filter(|name| !name.trim().is_empty()),
+        recordings_dir,
+        model: settings.model.clone(),
+    }
+}
+
+fn send_config(app: &AppHandle, settings: &AppSettings) {
+    let config = sidecar_config(app, settings);
+    backend_call_or_emit_error(app, |backend, id| backend.set_config(id, &config));
+}
+
+fn current_recording_mode(shared: &SharedState) -> RecordingMode {
+    shared
+        .settings
+        .lock()
+        .map(|settings| settings.recording_mode)
+        .unwrap_or_default()
+}
+
+fn handle_shortcut_event(app: &AppHandle, shortcut: &Shortcut, state: ShortcutState) {
+    let action = {
+        let shared = app.state::<SharedState>();
+        let actions = match shared.shortcut_actions.lock() {
+            Ok(guard) => guard,
+            Err(_) => return,
+        };
+        actions.get(&shortcut.id()).copied()
+    };
+    let Some(action) = action else {
+        return;
+    };
+
+    if action == ShortcutAction::Dictate {
+        handle_hotkey_event(app, state);
+        return;
+    }
+    if state != ShortcutState::Pressed {
+        return;
+    }
+
+    match action {
+        ShortcutAction::Dictate => {}
+        ShortcutAction::CancelJob => cancel_current(app.clone()),
+        ShortcutAction::RepasteLast => repaste_last_transcript(app),
+        ShortcutAction::OpenSettings => {
+            if let Err(e) = open_settings_window(app.clone()) {
+                log_line(app, &format!("failed to open settings from shortcut: {e}"));
+            }
+        }
+        ShortcutAction::ToggleMode => cycle_recording_mode(app),
+        ShortcutAction::RestoreClipboard => restore_saved_clipboard(app),
+    }
+}
+
+fn repaste_last_transcript(app: &AppHandle) {
+    let shared = app.state::<SharedState>();
+    let text = shared
+        .last_transcript
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone());
+    match text {
+        Some(text) => output_transcript(app, &text),
+        None => log_line(app, "no transcript to re-paste yet"),
+    }
+}
+
+fn cycle_recording_mode(app: &AppHandle) {
+    let shared = app.state::<SharedState>();
+    let updated = match shared.settings.lock() {
+        Ok(mut settings) => {
+            settings.recording_mode = settings.recording_mode.next();
+            settings.clone()
+        }
+        Err(_) => return,
+    };
+
+    if let Err(e) = save_settings_to_disk(app, &updated) {
+        log_line(app, &format!("failed to persist recording mode: {e}"));
+    }
+    log_line(
+        app,
+        &format!("recording mode switched to {:?}", updated.recording_mode),
     );
+    let _ = app.emit("settings_updated", &updated);
+}
+
+fn handle_hotkey_event<R: Runtime>(app: &AppHandle<R>, state: ShortcutState) {
+    let shared = app.state::<SharedState>();
+    let mode = current_recording_mode(&shared);
+    let recording = shared.recording_started.load(Ordering::SeqCst);
+    let now = Instant::now();
+
+    let action = match shared.hotkey_gesture.lock() {
+        Ok(mut gesture) => match state {
+            ShortcutState::Pressed => gesture.press(mode, recording, now),
+            ShortcutState::Released => gesture.release(mode, recording, now),
+        },
+        Err(_) => return,
+    };
+
+    match action {
+        HotkeyAction::StartRecording => handle_hotkey_start(app),
+        HotkeyAction::StopAndTranscribe => handle_hotkey_stop(app),
+        HotkeyAction::Nothing => {}
+    }
 }
 
-fn handle_hotkey_press(app: &AppHandle) {
+fn handle_hotkey_start<R: Runtime>(app: &AppHandle<R>) {
     let shared = app.state::<SharedState>();
 
     if shared
@@ -790,11 +2260,11 @@ fn handle_hotkey_press(app: &AppHandle) {
         .is_ok()
     {
         show_popup(app);
-        send_command_or_emit_error(app, json!({ "command": "start_recording" }));
+        backend_call_or_emit_error(app, |backend, id| backend.start(id));
     }
 }
 
-fn handle_hotkey_release(app: &AppHandle) {
+fn handle_hotkey_stop<R: Runtime>(app: &AppHandle<R>) {
     let shared = app.state::<SharedState>();
 
     if shared
@@ -803,7 +2273,7 @@ fn handle_hotkey_release(app: &AppHandle) {
         .is_ok()
     {
         show_popup(app);
-        send_command_or_emit_error(app, json!({ "command": "stop_and_transcribe" }));
+        backend_call_or_emit_error(app, |backend, id| backend.stop(id));
     }
 }
 
@@ -818,33 +2288,321 @@ fn get_settings(app: AppHandle) -> Result<AppSettings, String> {
 }
 
 #[tauri::command]
-fn save_settings(app: AppHandle, settings: AppSettings) -> Result<AppSettings, String> {
+fn save_settings(app: AppHandle, settings: AppSettings) -> Result<SavedSettings, String> {
     if settings.popup_timeout_sec == 0 || settings.popup_timeout_sec > 120 {
         return Err("popup timeout must be between 1 and 120 seconds".to_string());
     }
     if settings.model_keepalive_min == 0 || settings.model_keepalive_min > 240 {
         return Err("model keepalive must be between 1 and 240 minutes".to_string());
     }
+    if settings.clipboard_restore_sec > 3600 {
+        return Err("clipboard restore delay must be at most 3600 seconds".to_string());
+    }
+    if settings.history_limit > 100_000 {
+        return Err("history limit must be at most 100000 entries".to_string());
+    }
+    settings.vad.validate()?;
+    SpokenCommands::new(&default_commands(), &settings.spoken_commands)?;
+    validate_language_mode(
+        &settings.language_mode,
+        &sidecar_languages(&app.state::<SharedState>()),
+    )?;
+    if settings.audio_retention_mb > 1_000_000 || settings.audio_retention_days > 3650 {
+        return Err("audio retention must be at most 1000000 MB and 3650 days".to_string());
+    }
+    validate_model(&settings.model, &app.state::<SharedState>())?;
 
     validate_hotkey(&settings)?;
+    validate_keymap(&shortcut_bindings(&settings))?;
 
     save_settings_to_disk(&app, &settings)?;
-    register_shortcut(&app, current_hotkey(&settings))?;
+    let shortcuts = register_shortcuts(&app, &settings)?;
     apply_autostart(&app, settings.auto_launch)?;
 
     let shared = app.state::<SharedState>();
-    {
+    let (preload_turned_on, previous_model) = {
         let mut guard = shared
             .settings
             .lock()
             .map_err(|_| "failed to lock settings mutex".to_string())?;
-        *guard = settings.clone();
-    }
+        let turned_on = settings.preload_model && !guard.preload_model;
+        let previous_model = std::mem::replace(&mut *guard, settings.clone()).model;
+        (turned_on, previous_model)
+    };
 
-    send_config_to_sidecar(&app, &settings);
+    if let Err(e) = with_history(&app, |h| h.set_limit(settings.history_limit)) {
+        log_line(&app, &format!("failed to apply history limit: {e}"));
+    }
+    send_config(&app, &settings);
+    apply_recording_retention(&app);
+    if previous_model != settings.model {
+        reload_model(&app, &previous_model, &settings.model);
+    } else if preload_turned_on {
+        preload_model_if_enabled(&app);
+    }
 
     log_line(&app, "settings updated");
-    Ok(settings)
+    Ok(SavedSettings {
+        settings,
+        shortcuts,
+    })
+}
+
+fn validate_audio_path(path: &Path) -> Result<(), String> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    if !SUPPORTED_AUDIO_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(format!(
+            "unsupported audio file '{}': use WAV, FLAC or OGG",
+            path.display()
+        ));
+    }
+    if !path.is_file() {
+        return Err(format!("audio file not found: {}", path.display()));
+    }
+    Ok(())
+}
+
+/// `recording` is a stored recording to link the transcript to, as when retranscribing.
+fn start_file_transcription(
+    app: &AppHandle,
+    path: &Path,
+    recording: Option<String>,
+) -> Result<(), String> {
+    validate_audio_path(path)?;
+
+    // The sidecar cancels a running dictation before starting the file job.
+    let shared = app.state::<SharedState>();
+    shared.recording_started.store(false, Ordering::SeqCst);
+    show_popup(app);
+    let request_id = shared.requests.next_id();
+    // Link before sending: the transcript may arrive before the send returns.
+    if let Some(recording) = recording {
+        if let Ok(mut guard) = shared.saved_recording.lock() {
+            *guard = Some((Some(request_id), recording));
+        };
+    }
+    asr_backend(app)?.transcribe_file(request_id, path)?;
+    log_line(app, &format!("transcribing file {}", path.display()));
+    Ok(())
+}
+
+fn transcribe_file_or_emit_error(app: &AppHandle, path: &Path) {
+    if let Err(e) = start_file_transcription(app, path, None) {
+        log_line(app, &format!("file transcription failed: {e}"));
+        emit_asr_error(app, e);
+    }
+}
+
+fn pick_file_to_transcribe(app: &AppHandle) {
+    let app_handle = app.clone();
+    app.dialog()
+        .file()
+        .set_title("Transcribe audio file")
+        .add_filter("Audio", SUPPORTED_AUDIO_EXTENSIONS)
+        .pick_file(move |file| {
+            let Some(file) = file else {
+                return;
+            };
+            match file.into_path() {
+                Ok(path) => transcribe_file_or_emit_error(&app_handle, &path),
+                Err(e) => log_line(&app_handle, &format!("invalid file selection: {e}")),
+            }
+        });
+}
+
+#[tauri::command]
+fn transcribe_file(app: AppHandle, path: String) -> Result<(), String> {
+    start_file_transcription(&app, Path::new(&path), None)
+}
+
+/// Writes the last transcript, or a history entry, to `path`. Without a path the user picks one;
+/// returns `None` if they cancel.
+#[tauri::command(async)]
+fn export_transcript(
+    app: AppHandle,
+    format: ExportFormat,
+    path: Option<String>,
+    history_id: Option<u64>,
+) -> Result<Option<String>, String> {
+    let (text, segments) = match history_id {
+        // History keeps no timing, so history entries only export as TXT or JSON.
+        Some(id) => {
+            let text = with_history(&app, |h| {
+                h.get(id)
+                    .map(|entry| entry.text.clone())
+                    .ok_or_else(|| format!("history entry {id} not found"))
+            })?;
+            (text, Vec::new())
+        }
+        None => {
+            let shared = app.state::<SharedState>();
+            let text = shared
+                .last_transcript
+                .lock()
+                .map_err(|_| "failed to lock last transcript mutex".to_string())?
+                .clone()
+                .ok_or_else(|| "no transcript to export yet".to_string())?;
+            let segments = shared
+                .segments
+                .lock()
+                .map_err(|_| "failed to lock segments mutex".to_string())?
+                .segments();
+            (text, segments)
+        }
+    };
+
+    // Render first, so a transcript that cannot be exported fails before the save dialog.
+    let rendered = render_transcript(format, &text, &segments)?;
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let Some(file) = app
+                .dialog()
+                .file()
+                .set_title("Export transcript")
+                .set_file_name(format!("transcript.{}", format.extension()))
+                .add_filter(format.label(), &[format.extension()])
+                .blocking_save_file()
+            else {
+                return Ok(None);
+            };
+            file.into_path()
+                .map_err(|e| format!("invalid export path: {e}"))?
+        }
+    };
+
+    fs::write(&path, rendered).map_err(|e| format!("failed to write export: {e}"))?;
+    log_line(&app, &format!("exported transcript to {}", path.display()));
+    Ok(Some(path.display().to_string()))
+}
+
+#[tauri::command]
+fn get_last_segments(app: AppHandle) -> Result<Vec<TranscriptSegment>, String> {
+    let shared = app.state::<SharedState>();
+    let aggregator = shared
+        .segments
+        .lock()
+        .map_err(|_| "failed to lock segments mutex".to_string())?;
+    Ok(aggregator.segments())
+}
+
+#[tauri::command]
+fn list_history(app: AppHandle, limit: Option<usize>) -> Result<Vec<HistoryEntry>, String> {
+    with_history(&app, |h| Ok(h.list(limit)))
+}
+
+#[tauri::command]
+fn search_history(app: AppHandle, query: String) -> Result<Vec<HistoryEntry>, String> {
+    with_history(&app, |h| Ok(h.search(&query)))
+}
+
+#[tauri::command]
+fn copy_history_entry(app: AppHandle, id: u64) -> Result<(), String> {
+    let text = with_history(&app, |h| {
+        h.get(id)
+            .map(|entry| entry.text.clone())
+            .ok_or_else(|| format!("history entry {id} not found"))
+    })?;
+    (app.state::<SharedState>().text_sink)().set_clipboard_text(&text)
+}
+
+#[tauri::command]
+fn delete_history_entry(app: AppHandle, id: u64) -> Result<(), String> {
+    // The store hands back the recording if no other entry links to it; with_history deletes it.
+    if !with_history(&app, |h| h.delete(id))? {
+        return Err(format!("history entry {id} not found"));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn clear_history(app: AppHandle) -> Result<(), String> {
+    with_history(&app, |h| h.clear())?;
+    log_line(&app, "history cleared");
+    Ok(())
+}
+
+#[tauri::command]
+fn get_replacement_rules(app: AppHandle) -> Result<Vec<ReplacementRule>, String> {
+    load_rules(&replacements_path(&app)?)
+}
+
+#[tauri::command]
+fn save_replacement_rules(
+    app: AppHandle,
+    rules: Vec<ReplacementRule>,
+) -> Result<Vec<ReplacementRule>, String> {
+    let replacer = Replacer::new(&rules)?;
+    save_rules(&replacements_path(&app)?, &rules)?;
+    let shared = app.state::<SharedState>();
+    *shared
+        .replacer
+        .lock()
+        .map_err(|_| "failed to lock replacer mutex".to_string())? = replacer;
+    log_line(&app, &format!("saved {} replacement rule(s)", rules.len()));
+    Ok(rules)
+}
+
+/// Applies unsaved `rules` to `text`, for previewing edits in settings.
+#[tauri::command]
+fn preview_replacements(rules: Vec<ReplacementRule>, text: String) -> Result<String, String> {
+    Ok(Replacer::new(&rules)?.apply(&text))
+}
+
+/// Languages the sidecar transcribes, without "auto", for the language picker.
+#[tauri::command]
+fn get_supported_languages(app: AppHandle) -> Vec<String> {
+    sidecar_languages(&app.state::<SharedState>())
+}
+
+/// The built-in spoken command table, shown next to the user's own commands in settings.
+#[tauri::command]
+fn get_default_spoken_commands() -> Vec<SpokenCommand> {
+    default_commands()
+}
+
+/// Runs the model again on the recording stored with a history entry; the result becomes a
+/// new history entry linked to the same audio.
+#[tauri::command]
+fn retranscribe(app: AppHandle, history_id: u64) -> Result<(), String> {
+    let audio_path = with_history(&app, |h| {
+        let entry = h
+            .get(history_id)
+            .ok_or_else(|| format!("history entry {history_id} not found"))?;
+        entry
+            .audio_path
+            .clone()
+            .ok_or_else(|| "this transcript has no stored recording".to_string())
+    })?;
+    if !Path::new(&audio_path).is_file() {
+        return Err("the recording was removed by audio retention".to_string());
+    }
+
+    start_file_transcription(&app, Path::new(&audio_path), Some(audio_path.clone()))
+}
+
+#[tauri::command]
+fn get_shortcut_registrations(app: AppHandle) -> Result<Vec<ShortcutRegistration>, String> {
+    let shared = app.state::<SharedState>();
+    let registrations = shared
+        .shortcut_registrations
+        .lock()
+        .map_err(|_| "failed to lock shortcut registrations mutex".to_string())?;
+    Ok(registrations.clone())
+}
+
+#[tauri::command]
+fn get_sidecar_status(app: AppHandle) -> Result<SidecarStatusReport, String> {
+    let shared = app.state::<SharedState>();
+    let status = shared
+        .sidecar_status
+        .lock()
+        .map_err(|_| "failed to lock sidecar status mutex".to_string())?;
+    Ok(status.clone())
 }
 
 #[tauri::command]
@@ -874,7 +2632,7 @@ fn start_recording(app: AppHandle) {
     let shared = app.state::<SharedState>();
     shared.recording_started.store(true, Ordering::SeqCst);
     show_popup(&app);
-    send_command_or_emit_error(&app, json!({ "command": "start_recording" }));
+    backend_call_or_emit_error(&app, |backend, id| backend.start(id));
 }
 
 #[tauri::command]
@@ -882,48 +2640,127 @@ fn stop_and_transcribe(app: AppHandle) {
     let shared = app.state::<SharedState>();
     shared.recording_started.store(false, Ordering::SeqCst);
     show_popup(&app);
-    send_command_or_emit_error(&app, json!({ "command": "stop_and_transcribe" }));
+    backend_call_or_emit_error(&app, |backend, id| backend.stop(id));
 }
 
 #[tauri::command]
 fn cancel_current(app: AppHandle) {
     let shared = app.state::<SharedState>();
     shared.recording_started.store(false, Ordering::SeqCst);
-    send_command_or_emit_error(&app, json!({ "command": "cancel_current" }));
+    backend_call_or_emit_error(&app, |backend, id| backend.cancel(id));
 }
 
-#[tauri::command]
-fn healthcheck(app: AppHandle) {
-    send_command_or_emit_error(&app, json!({ "command": "healthcheck" }));
+#[derive(Debug, Clone, Serialize)]
+struct SavedSettings {
+    settings: AppSettings,
+    shortcuts: Vec<ShortcutRegistration>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct HealthReport {
+    device: String,
+    model: String,
+    round_trip_ms: u64,
+}
+
+#[tauri::command(async)]
+fn healthcheck(app: AppHandle) -> Result<HealthReport, String> {
+    let started = Instant::now();
+    match asr_backend(&app)?.healthcheck()? {
+        SidecarEvent::Metrics { device, model, .. } => Ok(HealthReport {
+            device,
+            model,
+            round_trip_ms: started.elapsed().as_millis() as u64,
+        }),
+        SidecarEvent::Error { message } => Err(message),
+        other => Err(format!("unexpected healthcheck reply: {other:?}")),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct InputDeviceList {
+    devices: Vec<InputDevice>,
+    default_id: Option<u32>,
+}
+
+#[tauri::command(async)]
+fn list_input_devices(app: AppHandle) -> Result<InputDeviceList, String> {
+    match asr_backend(&app)?.list_input_devices()? {
+        SidecarEvent::InputDevices {
+            devices,
+            default_id,
+        } => Ok(InputDeviceList {
+            devices,
+            default_id,
+        }),
+        SidecarEvent::Error { message } => Err(message),
+        other => Err(format!("unexpected input device reply: {other:?}")),
+    }
 }
 
-fn init_sidecar(app: &AppHandle, settings: &AppSettings) {
+#[derive(Debug, Clone, Serialize)]
+struct ModelList {
+    models: Vec<ModelInfo>,
+    current: String,
+}
+
+#[tauri::command(async)]
+fn list_models(app: AppHandle) -> Result<ModelList, String> {
+    match asr_backend(&app)?.list_models()? {
+        SidecarEvent::Models { models, current } => Ok(ModelList { models, current }),
+        SidecarEvent::Error { message } => Err(message),
+        other => Err(format!("unexpected model list reply: {other:?}")),
+    }
+}
+
+fn init_sidecar(app: &AppHandle) {
     let shared = app.state::<SharedState>();
 
+    // Spawning performs the init/ready handshake and pushes current config.
     if let Err(e) = ensure_sidecar_running(app, &shared) {
         log_line(app, &format!("failed to start sidecar at setup: {e}"));
-        emit_asr_event(app, &json!({ "event": "error", "message": e }));
-        return;
+        emit_asr_error(app, e);
     }
-
-    send_command_or_emit_error(app, json!({ "command": "init" }));
-    send_config_to_sidecar(app, settings);
 }
 
 fn build_tray(app: &AppHandle) -> Result<(), String> {
+    let status_item = MenuItem::with_id(app, "status", "ASR: Starting", false, None::<&str>)
+        .map_err(|e| format!("failed to create status menu item: {e}"))?;
+    let restore_item = MenuItem::with_id(
+        app,
+        "restore_clipboard",
+        "Restore clipboard",
+        true,
+        None::<&str>,
+    )
+    .map_err(|e| format!("failed to create restore clipboard menu item: {e}"))?;
+    let open_file_item = MenuItem::with_id(app, "open_file", "Open file…", true, None::<&str>)
+        .map_err(|e| format!("failed to create open file menu item: {e}"))?;
     let settings_item = MenuItem::with_id(app, "settings", "Settings", true, None::<&str>)
         .map_err(|e| format!("failed to create settings menu item: {e}"))?;
     let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)
         .map_err(|e| format!("failed to create quit menu item: {e}"))?;
 
-    let menu = Menu::with_items(app, &[&settings_item, &quit_item])
-        .map_err(|e| format!("failed to create tray menu: {e}"))?;
-
-    let tray = TrayIconBuilder::new()
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status_item,
+            &open_file_item,
+            &restore_item,
+            &settings_item,
+            &quit_item,
+        ],
+    )
+    .map_err(|e| format!("failed to create tray menu: {e}"))?;
+
+    let tray = TrayIconBuilder::with_id(TRAY_ID)
         .icon(TRAY_ICON.clone())
+        .tooltip("Sber Whisper")
         .menu(&menu)
         .show_menu_on_left_click(true)
         .on_menu_event(|app, event| match event.id.as_ref() {
+            "open_file" => pick_file_to_transcribe(app),
+            "restore_clipboard" => restore_saved_clipboard(app),
             "settings" => {
                 let _ = open_settings_window(app.clone());
             }
@@ -938,6 +2775,11 @@ fn build_tray(app: &AppHandle) -> Result<(), String> {
     // Tauri requires keeping TrayIcon handle alive; dropping it removes tray icon and may exit app.
     std::mem::forget(tray);
 
+    let shared = app.state::<SharedState>();
+    if let Ok(mut guard) = shared.tray_status_item.lock() {
+        *guard = Some(status_item);
+    };
+
     Ok(())
 }
 
@@ -970,10 +2812,24 @@ fn setup_app(app: &AppHandle) -> Result<(), String> {
     setup_windows(app);
     build_tray(app)?;
     validate_hotkey(&settings)?;
-    register_shortcut(app, current_hotkey(&settings))?;
+    if let Err(e) = validate_keymap(&shortcut_bindings(&settings)) {
+        log_line(app, &format!("keymap is invalid: {e}"));
+    }
+    register_shortcuts(app, &settings)?;
     apply_autostart(app, settings.auto_launch)?;
+    if let Err(e) = open_history(app, &settings) {
+        log_line(app, &format!("history unavailable: {e}"));
+    }
+    apply_recording_retention(app);
+    if let Err(e) = load_replacements(app) {
+        log_line(app, &format!("replacement rules unavailable: {e}"));
+    }
 
-    init_sidecar(app, &settings);
+    init_backend(app);
+    if !shared.sidecar_disabled.load(Ordering::SeqCst) {
+        init_sidecar(app);
+        spawn_sidecar_supervisor(app.clone());
+    }
     log_line(app, "application setup complete");
 
     Ok(())
@@ -983,6 +2839,7 @@ fn cleanup_sidecar(app: &AppHandle) {
     let proc_to_stop: Option<SidecarProcess> = {
         let shared = app.state::<SharedState>();
         shared.shutdown.store(true, Ordering::SeqCst);
+        wake_sidecar_supervisor(&shared);
         let taken = match shared.sidecar.lock() {
             Ok(mut guard) => guard.take(),
             Err(_) => None,
@@ -991,10 +2848,8 @@ fn cleanup_sidecar(app: &AppHandle) {
     };
 
     if let Some(mut proc) = proc_to_stop {
-        let _ = proc
-            .stdin
-            .write_all(format!("{}\n", json!({ "command": "shutdown" })).as_bytes());
-        let _ = proc.stdin.flush();
+        let id = app.state::<SharedState>().requests.next_id();
+        let _ = write_sidecar_command(&mut proc, id, &SidecarCommand::Shutdown);
         let _ = proc.child.kill();
         let _ = proc.child.wait();
     }
@@ -1005,20 +2860,22 @@ pub fn run() {
     tauri::Builder::default()
         .manage(SharedState::new(AppSettings::default()))
         .plugin(tauri_plugin_store::Builder::default().build())
-        .plugin(tauri_plugin_global_shortcut::Builder::new().with_handler(
-            |app, _shortcut, event| match event.state {
-                ShortcutState::Pressed => handle_hotkey_press(app),
-                ShortcutState::Released => handle_hotkey_release(app),
-            },
-        ).build())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(|app, shortcut, event| {
+                    handle_shortcut_event(app, shortcut, event.state)
+                })
+                .build(),
+        )
         .plugin(tauri_plugin_autostart::init(
             MacosLauncher::LaunchAgent,
             Some(vec!["--silent"]),
         ))
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
-            setup_app(&app.handle()).map_err(|e| -> Box<dyn std::error::Error> {
-                Box::new(std::io::Error::new(std::io::ErrorKind::Other, e))
+            setup_app(app.handle()).map_err(|e| -> Box<dyn std::error::Error> {
+                Box::new(std::io::Error::other(e))
             })?;
             Ok(())
         })
@@ -1032,6 +2889,24 @@ pub fn run() {
             stop_and_transcribe,
             cancel_current,
             healthcheck,
+            list_input_devices,
+            list_models,
+            get_sidecar_status,
+            get_shortcut_registrations,
+            list_history,
+            search_history,
+            copy_history_entry,
+            delete_history_entry,
+            clear_history,
+            retranscribe,
+            get_replacement_rules,
+            save_replacement_rules,
+            preview_replacements,
+            get_default_spoken_commands,
+            get_supported_languages,
+            transcribe_file,
+            get_last_segments,
+            export_transcript,
         ])
         .on_window_event(|window, event| {
             if window.label() == "popup" {
@@ -1046,12 +2921,22 @@ pub fn run() {
                     api.prevent_close();
                     let _ = window.hide();
                 }
+
+                // The sidecar runs one job at a time, so only the first dropped file is used.
+                if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) =
+                    event
+                {
+                    if let Some(path) = paths.first() {
+                        transcribe_file_or_emit_error(window.app_handle(), path);
+                    }
+                }
             }
         })
         .build(tauri::generate_context!())
         .expect("failed to build tauri app")
         .run(|app, event| {
             if let tauri::RunEvent::ExitRequested { .. } = event {
+                let _ = with_history(app, |h| h.flush_pending());
                 cleanup_sidecar(app);
             }
         });
@@ -1059,7 +2944,39 @@ pub fn run() {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_shortcut, AppSettings};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+    use tauri::{App, Manager};
+    use tauri_plugin_global_shortcut::ShortcutState;
+
+    use super::{
+        handle_hotkey_event, handle_sidecar_event, install_backend, parse_shortcut,
+        validate_audio_path, validate_model, AppSettings, MockBackend, RestartBackoff, SharedState,
+        SIDECAR_MAX_RESTARTS,
+    };
+    use crate::normalize::{NormalizationSettings, TerminalPunctuation};
+    use crate::output::fake::FakeSink;
+    use crate::protocol::SidecarEvent;
+
+    /// A windowless app around `state`; its config and logs go under the temp dir.
+    fn mock_app(mut state: SharedState) -> App<MockRuntime> {
+        static NEXT_APP: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "sber-whisper-tests-{}-{}",
+            std::process::id(),
+            NEXT_APP.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        state.config_dir = Some(dir);
+        mock_builder()
+            .manage(state)
+            .build(mock_context(noop_assets()))
+            .expect("failed to build mock app")
+    }
 
     #[test]
     fn settings_default_timeout_is_ten() {
@@ -1084,4 +3001,97 @@ mod tests {
         let parsed = parse_shortcut("not-a-hotkey");
         assert!(parsed.is_err());
     }
+
+    #[test]
+    fn restart_backoff_doubles_and_gives_up() {
+        let mut backoff = RestartBackoff::default();
+        let delays: Vec<Duration> = std::iter::from_fn(|| backoff.next_delay()).collect();
+        assert_eq!(delays.len(), SIDECAR_MAX_RESTARTS as usize);
+        assert_eq!(delays[0], Duration::from_secs(1));
+        assert_eq!(delays[1], Duration::from_secs(2));
+        assert!(delays.windows(2).all(|w| w[0] <= w[1]));
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn audio_path_needs_supported_existing_file() {
+        let err = validate_audio_path(Path::new("voice-note.mp3")).unwrap_err();
+        assert!(err.contains("unsupported"));
+        let err = validate_audio_path(Path::new("/nonexistent/voice-note.OGG")).unwrap_err();
+        assert!(err.contains("not found"));
+    }
+
+    #[test]
+    fn model_must_be_a_known_variant() {
+        let shared = SharedState::new(AppSettings::default());
+        // Before the sidecar advertised its models, the GigaAM v3 variants are assumed.
+        assert!(validate_model(&AppSettings::default().model, &shared).is_ok());
+        let err = validate_model("v3_tiny", &shared).unwrap_err();
+        assert!(err.contains("v3_ctc"));
+        assert!(validate_model(" ", &shared).is_err());
+
+        *shared.sidecar_models.lock().unwrap() = vec!["v4_ctc".to_string()];
+        assert!(validate_model("v4_ctc", &shared).is_ok());
+        assert!(validate_model("v3_ctc", &shared).is_err());
+    }
+
+    #[test]
+    fn segments_skip_sentence_stages() {
+        let settings = AppSettings {
+            normalization: NormalizationSettings {
+                normalize_whitespace: true,
+                capitalize_sentences: true,
+                terminal_punctuation: TerminalPunctuation::AddPeriod,
+                ..NormalizationSettings::default()
+            },
+            ..AppSettings::default()
+        };
+        let app = mock_app(SharedState::new(settings));
+        let app = app.handle();
+        for (index, text) in ["встреча  в пять", "потом обед "].into_iter().enumerate()
+        {
+            let segment = SidecarEvent::TranscriptSegment {
+                index: index as u32,
+                start_ms: index as u64 * 20_000,
+                end_ms: (index as u64 + 1) * 20_000,
+                text: text.to_string(),
+            };
+            handle_sidecar_event(app, Some(3), segment);
+        }
+
+        let shared = app.state::<SharedState>();
+        let segments = shared.segments.lock().unwrap();
+        let texts: Vec<String> = segments.segments().into_iter().map(|s| s.text).collect();
+        assert_eq!(texts, vec!["встреча в пять", "потом обед"]);
+        assert_eq!(segments.text(), "встреча в пять потом обед");
+    }
+
+    #[test]
+    fn hotkey_to_clipboard_flow() {
+        let sink = FakeSink::default();
+        let mut state = SharedState::new(AppSettings::default());
+        let delivered = sink.clone();
+        state.text_sink = Box::new(move || Box::new(delivered.clone()));
+        let app = mock_app(state);
+        let app = app.handle();
+        install_backend(app, Arc::new(MockBackend::new("привет мир")));
+        let shared = app.state::<SharedState>();
+
+        // Hold mode: press starts, release stops and transcribes.
+        handle_hotkey_event(app, ShortcutState::Pressed);
+        assert!(shared.recording_started.load(Ordering::SeqCst));
+        handle_hotkey_event(app, ShortcutState::Released);
+        assert!(!shared.recording_started.load(Ordering::SeqCst));
+
+        // Delivery runs on its own thread.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sink.calls().is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(sink.calls(), vec!["clipboard:привет мир"]);
+        let last_transcript = shared.last_transcript.lock().unwrap().clone();
+        assert_eq!(last_transcript.as_deref(), Some("привет мир"));
+    }
 }
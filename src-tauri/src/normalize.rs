@@ -0,0 +1,463 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+/// What to do with the end of a transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TerminalPunctuation {
+    /// Leave the ending as the model produced it.
+    #[default]
+    Keep,
+    /// End with a period unless the text already ends with `.`, `!`, `?` or `…`.
+    AddPeriod,
+    /// Drop a single trailing period, e.g. for chat messages.
+    RemovePeriod,
+}
+
+/// Text normalization stages run on every final transcript. All are off by default, so
+/// transcripts only change once the user turns a stage on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub(crate) struct NormalizationSettings {
+    #[serde(default)]
+    pub normalize_whitespace: bool,
+    /// "двадцать пять" becomes "25".
+    #[serde(default)]
+    pub spoken_numbers: bool,
+    #[serde(default)]
+    pub capitalize_sentences: bool,
+    #[serde(default)]
+    pub terminal_punctuation: TerminalPunctuation,
+}
+
+/// One step of the normalization pipeline.
+pub(crate) trait TextStage: Send {
+    fn apply(&self, text: &str) -> String;
+}
+
+/// Stages in a fixed order: whitespace, numbers, capitalization, terminal punctuation.
+pub(crate) struct Pipeline {
+    stages: Vec<Box<dyn TextStage>>,
+}
+
+impl Pipeline {
+    pub fn new(settings: &NormalizationSettings) -> Self {
+        let mut stages: Vec<Box<dyn TextStage>> = Vec::new();
+        if settings.normalize_whitespace {
+            stages.push(Box::new(Whitespace));
+        }
+        if settings.spoken_numbers {
+            stages.push(Box::new(SpokenNumbers));
+        }
+        if settings.capitalize_sentences {
+            stages.push(Box::new(Capitalize));
+        }
+        if settings.terminal_punctuation != TerminalPunctuation::Keep {
+            stages.push(Box::new(settings.terminal_punctuation));
+        }
+        Self { stages }
+    }
+
+    /// Only the stages that give the same result on a piece of a transcript: whitespace. Numbers,
+    /// capitalization and terminal punctuation depend on where the piece sits in the whole text.
+    pub fn for_segments(settings: &NormalizationSettings) -> Self {
+        let mut stages: Vec<Box<dyn TextStage>> = Vec::new();
+        if settings.normalize_whitespace {
+            stages.push(Box::new(Whitespace));
+        }
+        Self { stages }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        self.stages
+            .iter()
+            .fold(text.to_string(), |text, stage| stage.apply(&text))
+    }
+}
+
+/// Collapses runs of spaces, drops spaces before punctuation and trims every line.
+struct Whitespace;
+
+impl TextStage for Whitespace {
+    fn apply(&self, text: &str) -> String {
+        let lines: Vec<String> = text
+            .lines()
+            .map(|line| {
+                let mut out = String::with_capacity(line.len());
+                for word in line.split_whitespace() {
+                    // Punctuation sticks to the previous word, but ".NET" is a word of its own.
+                    let mut chars = word.chars();
+                    let attaches = chars.next().is_some_and(|c| {
+                        matches!(c, ',' | '.' | '!' | '?' | ':' | ';' | ')' | '…')
+                    }) && !chars.next().is_some_and(char::is_alphanumeric);
+                    if !out.is_empty() && !attaches {
+                        out.push(' ');
+                    }
+                    out.push_str(word);
+                }
+                out
+            })
+            .collect();
+        lines.join("\n").trim().to_string()
+    }
+}
+
+/// Upper-cases the first letter of the text and of every sentence or line. Words that already
+/// have a capital letter ("iPhone") are left as they are.
+struct Capitalize;
+
+/// Shortenings whose period does not end a sentence, besides dotted ones like "т.е." or "e.g.".
+const ABBREVIATIONS: &[&str] = &["см", "напр", "ср", "cf", "vs"];
+
+fn is_abbreviation(word: &str) -> bool {
+    let word = word
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    if ABBREVIATIONS.contains(&word.as_str()) {
+        return true;
+    }
+    word.contains('.')
+        && word
+            .split('.')
+            .all(|part| part.chars().count() == 1 && part.chars().all(char::is_alphabetic))
+}
+
+/// Whether `word` ends a sentence, looking past closing quotes and brackets.
+fn ends_sentence(word: &str) -> bool {
+    let word = word.trim_end_matches(['"', '»', ')']);
+    match word.chars().last() {
+        Some('!' | '?' | '…') => true,
+        Some('.') => !is_abbreviation(&word[..word.len() - 1]),
+        _ => false,
+    }
+}
+
+fn capitalize_word(word: &str) -> String {
+    if word.chars().any(char::is_uppercase) {
+        return word.to_string();
+    }
+    match word.char_indices().find(|(_, c)| c.is_alphabetic()) {
+        Some((i, c)) => {
+            let mut out = String::with_capacity(word.len());
+            out.push_str(&word[..i]);
+            out.extend(c.to_uppercase());
+            out.push_str(&word[i + c.len_utf8()..]);
+            out
+        }
+        None => word.to_string(),
+    }
+}
+
+impl TextStage for Capitalize {
+    fn apply(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut sentence_start = true;
+        for token in text.split_inclusive(char::is_whitespace) {
+            let word = token.trim_end();
+            if sentence_start {
+                out.push_str(&capitalize_word(word));
+            } else {
+                out.push_str(word);
+            }
+            out.push_str(&token[word.len()..]);
+
+            // Quotes and dashes before the first word keep the sentence open.
+            if word.chars().any(char::is_alphanumeric) {
+                sentence_start = ends_sentence(word);
+            }
+            if token.ends_with('\n') {
+                sentence_start = true;
+            }
+        }
+        out
+    }
+}
+
+impl TextStage for TerminalPunctuation {
+    fn apply(&self, text: &str) -> String {
+        let trimmed = text.trim_end();
+        match self {
+            Self::Keep => text.to_string(),
+            Self::AddPeriod => match trimmed.chars().last() {
+                Some(c) if c.is_alphanumeric() || matches!(c, '"' | '»' | ')') => {
+                    format!("{trimmed}.")
+                }
+                _ => trimmed.to_string(),
+            },
+            Self::RemovePeriod => match trimmed.strip_suffix('.') {
+                Some(rest) if !rest.ends_with('.') => rest.to_string(),
+                _ => trimmed.to_string(),
+            },
+        }
+    }
+}
+
+/// Converts Russian cardinal numbers in the nominative case to digits.
+struct SpokenNumbers;
+
+enum NumberWord {
+    /// 0-9, 10-19, 20-90 or 100-900.
+    Value(u64),
+    /// тысяча, миллион, миллиард.
+    Scale(u64),
+}
+
+fn number_word(word: &str) -> Option<NumberWord> {
+    use NumberWord::{Scale, Value};
+    let word = match word {
+        "ноль" => Value(0),
+        "один" | "одна" | "одно" => Value(1),
+        "два" | "две" => Value(2),
+        "три" => Value(3),
+        "четыре" => Value(4),
+        "пять" => Value(5),
+        "шесть" => Value(6),
+        "семь" => Value(7),
+        "восемь" => Value(8),
+        "девять" => Value(9),
+        "десять" => Value(10),
+        "одиннадцать" => Value(11),
+        "двенадцать" => Value(12),
+        "тринадцать" => Value(13),
+        "четырнадцать" => Value(14),
+        "пятнадцать" => Value(15),
+        "шестнадцать" => Value(16),
+        "семнадцать" => Value(17),
+        "восемнадцать" => Value(18),
+        "девятнадцать" => Value(19),
+        "двадцать" => Value(20),
+        "тридцать" => Value(30),
+        "сорок" => Value(40),
+        "пятьдесят" => Value(50),
+        "шестьдесят" => Value(60),
+        "семьдесят" => Value(70),
+        "восемьдесят" => Value(80),
+        "девяносто" => Value(90),
+        "сто" => Value(100),
+        "двести" => Value(200),
+        "триста" => Value(300),
+        "четыреста" => Value(400),
+        "пятьсот" => Value(500),
+        "шестьсот" => Value(600),
+        "семьсот" => Value(700),
+        "восемьсот" => Value(800),
+        "девятьсот" => Value(900),
+        "тысяча" | "тысячи" | "тысяч" => Scale(1_000),
+        "миллион" | "миллиона" | "миллионов" => Scale(1_000_000),
+        "миллиард" | "миллиарда" | "миллиардов" => Scale(1_000_000_000),
+        _ => return None,
+    };
+    Some(word)
+}
+
+/// A number being read word by word, e.g. "две тысячи двадцать пять".
+#[derive(Default)]
+struct NumberReader {
+    words: Vec<String>,
+    total: u64,
+    group: u64,
+    last_scale: Option<u64>,
+    zero: bool,
+}
+
+impl NumberReader {
+    /// Adds `word` if it continues the number; returns false if it starts a new one.
+    fn push(&mut self, lower: &str, word: &str) -> bool {
+        let Some(number) = number_word(lower) else {
+            return false;
+        };
+        if self.zero {
+            return false;
+        }
+        let fits = match number {
+            NumberWord::Value(0) => self.words.is_empty(),
+            NumberWord::Value(v) if v >= 100 => self.group == 0,
+            NumberWord::Value(v) if v >= 10 => self.group.is_multiple_of(100),
+            NumberWord::Value(_) => {
+                self.group.is_multiple_of(10) && !(10..20).contains(&(self.group % 100))
+            }
+            NumberWord::Scale(scale) => self.last_scale.is_none_or(|last| scale < last),
+        };
+        if !fits {
+            return false;
+        }
+        match number {
+            NumberWord::Value(0) => self.zero = true,
+            NumberWord::Value(v) => self.group += v,
+            NumberWord::Scale(scale) => {
+                self.total += self.group.max(1) * scale;
+                self.group = 0;
+                self.last_scale = Some(scale);
+            }
+        }
+        self.words.push(word.to_string());
+        true
+    }
+
+    /// The digits, or the original words for a lone "один", which is usually not a number.
+    fn finish(&mut self) -> Option<String> {
+        let reader = std::mem::take(self);
+        if reader.words.is_empty() {
+            return None;
+        }
+        let lone_one = reader.words.len() == 1 && reader.group == 1 && reader.total == 0;
+        if lone_one {
+            return Some(reader.words[0].clone());
+        }
+        Some((reader.total + reader.group).to_string())
+    }
+}
+
+impl TextStage for SpokenNumbers {
+    /// Replaces only the spans of number words; the text around them, spacing included, stays.
+    fn apply(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut copied = 0;
+        let mut finish = |reader: &mut NumberReader, span: &Range<usize>| {
+            if let Some(number) = reader.finish() {
+                out.push_str(&text[copied..span.start]);
+                out.push_str(&number);
+                copied = span.end;
+            }
+        };
+
+        let mut reader = NumberReader::default();
+        // Byte range of the words the reader holds.
+        let mut span = 0..0;
+        for token in text.split_whitespace() {
+            let start = token.as_ptr() as usize - text.as_ptr() as usize;
+            let word = token.trim_end_matches(|c: char| !c.is_alphanumeric());
+            let lower = word.to_lowercase();
+
+            // A number does not run on into the next line.
+            if text[span.end..start].contains('\n') {
+                finish(&mut reader, &span);
+            }
+            if !reader.push(&lower, word) {
+                finish(&mut reader, &span);
+                if !reader.push(&lower, word) {
+                    continue;
+                }
+            }
+            if reader.words.len() == 1 {
+                span.start = start;
+            }
+            span.end = start + word.len();
+            // Punctuation after a number word ends the number.
+            if word.len() < token.len() {
+                finish(&mut reader, &span);
+            }
+        }
+        finish(&mut reader, &span);
+        out.push_str(&text[copied..]);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Capitalize, NormalizationSettings, Pipeline, SpokenNumbers, TerminalPunctuation, TextStage,
+        Whitespace,
+    };
+
+    fn check(stage: &dyn TextStage, cases: &[(&str, &str)]) {
+        for (input, expected) in cases {
+            assert_eq!(stage.apply(input), *expected, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn whitespace() {
+        check(
+            &Whitespace,
+            &[
+                ("  привет   мир  ", "привет мир"),
+                ("да , конечно !", "да, конечно!"),
+                ("пишу на .NET и C#", "пишу на .NET и C#"),
+                ("ну ... ладно", "ну... ладно"),
+                ("первая  строка \n  вторая", "первая строка\nвторая"),
+                ("", ""),
+            ],
+        );
+    }
+
+    #[test]
+    fn capitalization() {
+        check(
+            &Capitalize,
+            &[
+                ("привет. как дела? хорошо", "Привет. Как дела? Хорошо"),
+                ("строка\nещё строка", "Строка\nЕщё строка"),
+                ("«цитата» здесь", "«Цитата» здесь"),
+                ("версия 2.5 вышла", "Версия 2.5 вышла"),
+                ("iPhone. новый", "iPhone. Новый"),
+                ("это т.е. пример. e.g. тоже", "Это т.е. пример. E.g. тоже"),
+                ("см. выше", "См. выше"),
+                ("2 яблока", "2 яблока"),
+                ("— да! — нет", "— Да! — Нет"),
+            ],
+        );
+    }
+
+    #[test]
+    fn terminal_punctuation() {
+        check(
+            &TerminalPunctuation::AddPeriod,
+            &[
+                ("готово", "готово."),
+                ("готово.", "готово."),
+                ("правда?", "правда?"),
+                ("итак…", "итак…"),
+                ("", ""),
+            ],
+        );
+        check(
+            &TerminalPunctuation::RemovePeriod,
+            &[
+                ("готово.", "готово"),
+                ("и так далее...", "и так далее..."),
+                ("правда?", "правда?"),
+            ],
+        );
+    }
+
+    #[test]
+    fn spoken_numbers() {
+        check(
+            &SpokenNumbers,
+            &[
+                ("двадцать пять рублей", "25 рублей"),
+                ("две тысячи двадцать пять", "2025"),
+                ("сто один", "101"),
+                ("тысяча двести", "1200"),
+                ("три миллиона пятьсот тысяч", "3500000"),
+                ("один из нас", "один из нас"),
+                ("два три", "2 3"),
+                ("девятнадцать пять", "19 5"),
+                ("ноль пять", "0 5"),
+                ("Пять, шесть.", "5, 6."),
+                ("позвони через десять минут", "позвони через 10 минут"),
+                ("двадцать  пять\n  рублей,   три", "25\n  рублей,   3"),
+                ("двадцать\nпять\n", "20\n5\n"),
+            ],
+        );
+    }
+
+    #[test]
+    fn pipeline_runs_enabled_stages_in_order() {
+        let settings = NormalizationSettings {
+            normalize_whitespace: true,
+            spoken_numbers: true,
+            capitalize_sentences: true,
+            terminal_punctuation: TerminalPunctuation::AddPeriod,
+        };
+        assert_eq!(
+            Pipeline::new(&settings).apply("  встреча в  пять . потом   обед"),
+            "Встреча в 5. Потом обед."
+        );
+
+        let off = NormalizationSettings::default();
+        assert_eq!(Pipeline::new(&off).apply(" как есть "), " как есть ");
+    }
+}
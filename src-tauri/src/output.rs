@@ -0,0 +1,381 @@
+use std::thread;
+use std::time::Duration;
+
+use arboard::{Clipboard, ImageData};
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use serde::{Deserialize, Serialize};
+
+/// Time the focused app gets to read the clipboard before the previous contents come back.
+const PASTE_SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OutputMode {
+    /// Only put the transcript on the clipboard.
+    #[default]
+    Clipboard,
+    /// Put the transcript on the clipboard and send the paste shortcut.
+    Paste,
+    /// Type the transcript as keystrokes, leaving the clipboard alone.
+    Type,
+}
+
+/// Clipboard contents saved before a transcript overwrites them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ClipboardSnapshot {
+    Text(String),
+    Image {
+        width: usize,
+        height: usize,
+        bytes: Vec<u8>,
+    },
+}
+
+/// What `deliver_text` did to the clipboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ClipboardOutcome {
+    Untouched,
+    /// The clipboard now holds the transcript; carries what was there before.
+    Replaced(Option<ClipboardSnapshot>),
+}
+
+/// Where a finished transcript ends up. The system sink talks to the OS; tests use a fake.
+pub(crate) trait TextSink {
+    fn clipboard_snapshot(&mut self) -> Option<ClipboardSnapshot>;
+    fn restore_clipboard(&mut self, snapshot: &ClipboardSnapshot) -> Result<(), String>;
+    fn set_clipboard_text(&mut self, text: &str) -> Result<(), String>;
+    fn paste(&mut self) -> Result<(), String>;
+    fn type_text(&mut self, text: &str) -> Result<(), String>;
+
+    fn wait_for_paste(&mut self) {
+        thread::sleep(PASTE_SETTLE_DELAY);
+    }
+}
+
+/// Makes the sink for each delivery; the app holds one so tests can hand it a fake.
+pub(crate) type TextSinkFactory = Box<dyn Fn() -> Box<dyn TextSink> + Send + Sync>;
+
+pub(crate) fn deliver_text(
+    sink: &mut dyn TextSink,
+    mode: OutputMode,
+    text: &str,
+    restore_clipboard: bool,
+) -> Result<ClipboardOutcome, String> {
+    match mode {
+        OutputMode::Clipboard => {
+            let previous = sink.clipboard_snapshot();
+            sink.set_clipboard_text(text)?;
+            Ok(ClipboardOutcome::Replaced(previous))
+        }
+        OutputMode::Type => {
+            sink.type_text(text)?;
+            Ok(ClipboardOutcome::Untouched)
+        }
+        OutputMode::Paste => {
+            let previous = sink.clipboard_snapshot();
+            sink.set_clipboard_text(text)?;
+            sink.paste()?;
+
+            match previous {
+                Some(previous) if restore_clipboard => {
+                    sink.wait_for_paste();
+                    sink.restore_clipboard(&previous)?;
+                    Ok(ClipboardOutcome::Untouched)
+                }
+                previous => Ok(ClipboardOutcome::Replaced(previous)),
+            }
+        }
+    }
+}
+
+/// Remembers the user's own clipboard contents across consecutive transcripts, so that
+/// dictating twice in a row still restores what the user had copied, not the first transcript.
+#[derive(Debug, Default)]
+pub(crate) struct ClipboardKeeper {
+    saved: Option<ClipboardSnapshot>,
+    written: Option<String>,
+    generation: u64,
+}
+
+impl ClipboardKeeper {
+    /// Records that `written` replaced `previous` on the clipboard. Returns a generation number
+    /// for `take_if_current`.
+    pub fn record(&mut self, previous: Option<ClipboardSnapshot>, written: &str) -> u64 {
+        let previous_is_ours = matches!(
+            (&previous, &self.written),
+            (Some(ClipboardSnapshot::Text(prev)), Some(ours)) if prev == ours
+        );
+        if !previous_is_ours {
+            self.saved = previous;
+        }
+        self.written = Some(written.to_string());
+        self.generation += 1;
+        self.generation
+    }
+
+    pub fn take(&mut self) -> Option<ClipboardSnapshot> {
+        self.written = None;
+        self.saved.take()
+    }
+
+    /// Like `take`, but only if no newer transcript was recorded and the clipboard still holds
+    /// the transcript, so a timed restore never clobbers something the user copied since.
+    pub fn take_if_current(
+        &mut self,
+        generation: u64,
+        current: Option<&ClipboardSnapshot>,
+    ) -> Option<ClipboardSnapshot> {
+        let still_ours = match (current, &self.written) {
+            (Some(ClipboardSnapshot::Text(text)), Some(ours)) => text == ours,
+            _ => false,
+        };
+        if generation != self.generation || !still_ours {
+            return None;
+        }
+        self.take()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct SystemTextSink {
+    enigo: Option<Enigo>,
+}
+
+impl SystemTextSink {
+    fn enigo(&mut self) -> Result<&mut Enigo, String> {
+        if self.enigo.is_none() {
+            let enigo = Enigo::new(&Settings::default())
+                .map_err(|e| format!("failed to initialize keyboard input: {e}"))?;
+            self.enigo = Some(enigo);
+        }
+        Ok(self.enigo.as_mut().expect("enigo was just initialized"))
+    }
+}
+
+impl TextSink for SystemTextSink {
+    fn clipboard_snapshot(&mut self) -> Option<ClipboardSnapshot> {
+        let mut clipboard = Clipboard::new().ok()?;
+        if let Ok(text) = clipboard.get_text() {
+            return Some(ClipboardSnapshot::Text(text));
+        }
+        clipboard
+            .get_image()
+            .ok()
+            .map(|image| ClipboardSnapshot::Image {
+                width: image.width,
+                height: image.height,
+                bytes: image.bytes.into_owned(),
+            })
+    }
+
+    fn restore_clipboard(&mut self, snapshot: &ClipboardSnapshot) -> Result<(), String> {
+        match snapshot {
+            ClipboardSnapshot::Text(text) => self.set_clipboard_text(text),
+            ClipboardSnapshot::Image {
+                width,
+                height,
+                bytes,
+            } => Clipboard::new()
+                .and_then(|mut cb| {
+                    cb.set_image(ImageData {
+                        width: *width,
+                        height: *height,
+                        bytes: bytes.as_slice().into(),
+                    })
+                })
+                .map_err(|e| format!("failed to restore clipboard image: {e}")),
+        }
+    }
+
+    fn set_clipboard_text(&mut self, text: &str) -> Result<(), String> {
+        Clipboard::new()
+            .and_then(|mut cb| cb.set_text(text.to_string()))
+            .map_err(|e| format!("clipboard copy failed: {e}"))
+    }
+
+    fn paste(&mut self) -> Result<(), String> {
+        #[cfg(target_os = "macos")]
+        let modifier = Key::Meta;
+        #[cfg(not(target_os = "macos"))]
+        let modifier = Key::Control;
+
+        let enigo = self.enigo()?;
+        let result = enigo
+            .key(modifier, Direction::Press)
+            .and_then(|_| click_v_key(enigo));
+        // Always release the modifier, otherwise it stays stuck for the user.
+        let release = enigo.key(modifier, Direction::Release);
+        result
+            .and(release)
+            .map_err(|e| format!("failed to send paste shortcut: {e}"))
+    }
+
+    fn type_text(&mut self, text: &str) -> Result<(), String> {
+        self.enigo()?
+            .text(text)
+            .map_err(|e| format!("failed to type transcript: {e}"))
+    }
+}
+
+/// A clipboard and keyboard in memory. Clones share state, so a test keeps one clone while the
+/// code under test writes to another.
+/// Presses the physical V key. `Key::Unicode('v')` follows the active layout,
+/// so with e.g. a Russian layout it becomes "м" and the shortcut is not a paste.
+#[cfg(target_os = "windows")]
+fn click_v_key(enigo: &mut Enigo) -> enigo::InputResult<()> {
+    const VK_V: u32 = 0x56;
+    enigo.key(Key::Other(VK_V), Direction::Click)
+}
+
+#[cfg(target_os = "macos")]
+fn click_v_key(enigo: &mut Enigo) -> enigo::InputResult<()> {
+    const KVK_ANSI_V: u16 = 0x09;
+    enigo.raw(KVK_ANSI_V, Direction::Click)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn click_v_key(enigo: &mut Enigo) -> enigo::InputResult<()> {
+    const X11_KEYCODE_V: u16 = 55;
+    enigo.raw(X11_KEYCODE_V, Direction::Click)
+}
+
+#[cfg(test)]
+pub(crate) mod fake {
+    use std::sync::{Arc, Mutex};
+
+    use super::{ClipboardSnapshot, TextSink};
+
+    #[derive(Default)]
+    struct FakeState {
+        clipboard: Option<ClipboardSnapshot>,
+        calls: Vec<String>,
+    }
+
+    #[derive(Clone, Default)]
+    pub(crate) struct FakeSink {
+        state: Arc<Mutex<FakeState>>,
+    }
+
+    impl FakeSink {
+        pub fn with_clipboard(snapshot: ClipboardSnapshot) -> Self {
+            let sink = Self::default();
+            sink.state.lock().unwrap().clipboard = Some(snapshot);
+            sink
+        }
+
+        pub fn clipboard(&self) -> Option<ClipboardSnapshot> {
+            self.state.lock().unwrap().clipboard.clone()
+        }
+
+        /// Everything done to the sink so far, e.g. `"clipboard:text"`, `"paste"`, `"restore"`.
+        pub fn calls(&self) -> Vec<String> {
+            self.state.lock().unwrap().calls.clone()
+        }
+
+        fn record(&self, call: String, clipboard: Option<ClipboardSnapshot>) {
+            let mut state = self.state.lock().unwrap();
+            state.calls.push(call);
+            if clipboard.is_some() {
+                state.clipboard = clipboard;
+            }
+        }
+    }
+
+    impl TextSink for FakeSink {
+        fn clipboard_snapshot(&mut self) -> Option<ClipboardSnapshot> {
+            self.clipboard()
+        }
+
+        fn restore_clipboard(&mut self, snapshot: &ClipboardSnapshot) -> Result<(), String> {
+            self.record("restore".to_string(), Some(snapshot.clone()));
+            Ok(())
+        }
+
+        fn set_clipboard_text(&mut self, text: &str) -> Result<(), String> {
+            let snapshot = ClipboardSnapshot::Text(text.to_string());
+            self.record(format!("clipboard:{text}"), Some(snapshot));
+            Ok(())
+        }
+
+        fn paste(&mut self) -> Result<(), String> {
+            self.record("paste".to_string(), None);
+            Ok(())
+        }
+
+        fn type_text(&mut self, text: &str) -> Result<(), String> {
+            self.record(format!("type:{text}"), None);
+            Ok(())
+        }
+
+        fn wait_for_paste(&mut self) {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fake::FakeSink;
+    use super::{deliver_text, ClipboardKeeper, ClipboardOutcome, ClipboardSnapshot, OutputMode};
+
+    fn text_snapshot(text: &str) -> ClipboardSnapshot {
+        ClipboardSnapshot::Text(text.to_string())
+    }
+
+    fn image_snapshot() -> ClipboardSnapshot {
+        ClipboardSnapshot::Image {
+            width: 1,
+            height: 1,
+            bytes: vec![0, 0, 0, 255],
+        }
+    }
+
+    #[test]
+    fn clipboard_and_type_modes() {
+        let mut sink = FakeSink::with_clipboard(image_snapshot());
+        let outcome = deliver_text(&mut sink, OutputMode::Clipboard, "привет", true).unwrap();
+        assert_eq!(outcome, ClipboardOutcome::Replaced(Some(image_snapshot())));
+
+        let outcome = deliver_text(&mut sink, OutputMode::Type, "мир", true).unwrap();
+        assert_eq!(outcome, ClipboardOutcome::Untouched);
+        assert_eq!(sink.calls(), vec!["clipboard:привет", "type:мир"]);
+        assert_eq!(sink.clipboard(), Some(text_snapshot("привет")));
+    }
+
+    #[test]
+    fn paste_restores_previous_clipboard_when_asked() {
+        let mut sink = FakeSink::with_clipboard(image_snapshot());
+        let outcome = deliver_text(&mut sink, OutputMode::Paste, "new", true).unwrap();
+        assert_eq!(outcome, ClipboardOutcome::Untouched);
+        assert_eq!(sink.calls(), vec!["clipboard:new", "paste", "restore"]);
+        assert_eq!(sink.clipboard(), Some(image_snapshot()));
+
+        let mut sink = FakeSink::with_clipboard(text_snapshot("old"));
+        let outcome = deliver_text(&mut sink, OutputMode::Paste, "new", false).unwrap();
+        assert_eq!(
+            outcome,
+            ClipboardOutcome::Replaced(Some(text_snapshot("old")))
+        );
+        assert_eq!(sink.calls(), vec!["clipboard:new", "paste"]);
+    }
+
+    #[test]
+    fn keeper_survives_consecutive_transcripts() {
+        let mut keeper = ClipboardKeeper::default();
+        keeper.record(Some(text_snapshot("user")), "first");
+        let generation = keeper.record(Some(text_snapshot("first")), "second");
+
+        // The user copied something else meanwhile: a timed restore must not clobber it.
+        assert_eq!(
+            keeper.take_if_current(generation, Some(&text_snapshot("copied later"))),
+            None
+        );
+        assert_eq!(
+            keeper.take_if_current(generation - 1, Some(&text_snapshot("second"))),
+            None
+        );
+        assert_eq!(
+            keeper.take_if_current(generation, Some(&text_snapshot("second"))),
+            Some(text_snapshot("user"))
+        );
+        assert_eq!(keeper.take(), None);
+    }
+}
@@ -0,0 +1,176 @@
+use std::time::{Duration, Instant};
+
+/// Minimum gap between two partial transcripts sent to the popup.
+pub(crate) const PARTIAL_MIN_INTERVAL: Duration = Duration::from_millis(150);
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum PartialDecision {
+    /// Send this text now.
+    Emit(String),
+    /// Too soon; the text is kept and should be flushed after `delay` with this `generation`.
+    FlushAfter { delay: Duration, generation: u64 },
+    /// Nothing to do: a flush is already scheduled or the text did not change.
+    Wait,
+}
+
+/// Coalesces partial transcripts: at most one per interval reaches the popup, and a text
+/// that arrives too early replaces any older one still waiting.
+#[derive(Debug)]
+pub(crate) struct PartialThrottle {
+    interval: Duration,
+    last_emit: Option<Instant>,
+    last_text: Option<String>,
+    pending: Option<String>,
+    flush_scheduled: bool,
+    /// Bumped by `reset`, so a flush scheduled before it finds nothing to send.
+    generation: u64,
+}
+
+impl Default for PartialThrottle {
+    fn default() -> Self {
+        Self::new(PARTIAL_MIN_INTERVAL)
+    }
+}
+
+impl PartialThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_emit: None,
+            last_text: None,
+            pending: None,
+            flush_scheduled: false,
+            generation: 0,
+        }
+    }
+
+    pub fn offer(&mut self, text: String, now: Instant) -> PartialDecision {
+        if self.pending.is_none() && self.last_text.as_deref() == Some(text.as_str()) {
+            return PartialDecision::Wait;
+        }
+
+        let elapsed = self
+            .last_emit
+            .map(|at| now.saturating_duration_since(at))
+            .unwrap_or(self.interval);
+        if elapsed >= self.interval && !self.flush_scheduled {
+            self.pending = None;
+            return PartialDecision::Emit(self.record(text, now));
+        }
+
+        self.pending = Some(text);
+        if self.flush_scheduled {
+            return PartialDecision::Wait;
+        }
+        self.flush_scheduled = true;
+        PartialDecision::FlushAfter {
+            delay: self.interval.saturating_sub(elapsed),
+            generation: self.generation,
+        }
+    }
+
+    /// Called when a scheduled flush fires; returns the newest text still waiting, unless the
+    /// throttle was reset since the flush was scheduled.
+    pub fn flush(&mut self, generation: u64, now: Instant) -> Option<String> {
+        if generation != self.generation {
+            return None;
+        }
+        self.flush_scheduled = false;
+        let text = self.pending.take()?;
+        Some(self.record(text, now))
+    }
+
+    /// Drops anything waiting, so no stale partial shows up after the final transcript.
+    pub fn reset(&mut self) {
+        self.last_emit = None;
+        self.last_text = None;
+        self.pending = None;
+        self.flush_scheduled = false;
+        self.generation += 1;
+    }
+
+    fn record(&mut self, text: String, now: Instant) -> String {
+        self.last_emit = Some(now);
+        self.last_text = Some(text.clone());
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{PartialDecision, PartialThrottle};
+
+    #[test]
+    fn coalesces_partials_within_interval() {
+        let mut throttle = PartialThrottle::new(Duration::from_millis(100));
+        let t0 = Instant::now();
+
+        assert_eq!(
+            throttle.offer("при".to_string(), t0),
+            PartialDecision::Emit("при".to_string())
+        );
+        assert_eq!(
+            throttle.offer("привет".to_string(), t0 + Duration::from_millis(30)),
+            PartialDecision::FlushAfter {
+                delay: Duration::from_millis(70),
+                generation: 0
+            }
+        );
+        assert_eq!(
+            throttle.offer("привет мир".to_string(), t0 + Duration::from_millis(60)),
+            PartialDecision::Wait
+        );
+        assert_eq!(
+            throttle.flush(0, t0 + Duration::from_millis(100)),
+            Some("привет мир".to_string())
+        );
+        assert_eq!(throttle.flush(0, t0 + Duration::from_millis(200)), None);
+
+        assert_eq!(
+            throttle.offer("привет мир".to_string(), t0 + Duration::from_millis(300)),
+            PartialDecision::Wait
+        );
+    }
+
+    #[test]
+    fn reset_drops_pending_partial() {
+        let mut throttle = PartialThrottle::new(Duration::from_millis(100));
+        let t0 = Instant::now();
+        throttle.offer("раз".to_string(), t0);
+        throttle.offer("раз два".to_string(), t0 + Duration::from_millis(10));
+
+        throttle.reset();
+        assert_eq!(throttle.flush(0, t0 + Duration::from_millis(100)), None);
+        assert_eq!(
+            throttle.offer("раз".to_string(), t0 + Duration::from_millis(110)),
+            PartialDecision::Emit("раз".to_string())
+        );
+    }
+
+    #[test]
+    fn flush_scheduled_before_reset_does_not_block_or_leak() {
+        let mut throttle = PartialThrottle::new(Duration::from_millis(100));
+        let t0 = Instant::now();
+        throttle.offer("старый".to_string(), t0);
+        throttle.offer("старый текст".to_string(), t0 + Duration::from_millis(10));
+        throttle.reset();
+
+        // The first partial after a reset goes out at once instead of waiting on the old flush.
+        assert_eq!(
+            throttle.offer("новый".to_string(), t0 + Duration::from_millis(20)),
+            PartialDecision::Emit("новый".to_string())
+        );
+        let PartialDecision::FlushAfter { generation, .. } =
+            throttle.offer("новый текст".to_string(), t0 + Duration::from_millis(30))
+        else {
+            panic!("expected a scheduled flush");
+        };
+        assert_eq!(throttle.flush(0, t0 + Duration::from_millis(100)), None);
+        assert_eq!(
+            throttle.flush(generation, t0 + Duration::from_millis(120)),
+            Some("новый текст".to_string())
+        );
+    }
+}
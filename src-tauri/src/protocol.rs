@@ -0,0 +1,689 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Bumped whenever the JSON-lines contract with `asr_service.py` changes incompatibly.
+pub(crate) const PROTOCOL_VERSION: u32 = 2;
+
+/// Runtime config pushed to the sidecar with `set_config`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SidecarConfig {
+    pub language_mode: String,
+    pub popup_timeout_sec: u64,
+    pub model_keepalive_min: u64,
+    /// Decode while recording and send real `partial_transcript` events.
+    #[serde(default)]
+    pub streaming_partials: bool,
+    /// Trailing silence after speech that ends a recording; 0 disables VAD.
+    #[serde(default)]
+    pub vad_silence_ms: u64,
+    #[serde(default)]
+    pub vad_threshold_dbfs: f32,
+    /// Name of the microphone to record from; `None` uses the system default.
+    #[serde(default)]
+    pub input_device: Option<String>,
+    /// Where the sidecar stores each recording; `None` keeps no audio.
+    #[serde(default)]
+    pub recordings_dir: Option<String>,
+    /// GigaAM variant to load, as listed by `list_models`.
+    #[serde(default)]
+    pub model: String,
+}
+
+/// A microphone as reported by `input_devices`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct InputDevice {
+    /// PortAudio index; only valid until devices change, so settings store the name.
+    pub id: u32,
+    pub name: String,
+    pub sample_rate: u32,
+    pub channels: u32,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// A GigaAM variant as reported by `models`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ModelInfo {
+    pub name: String,
+    /// "ctc" decodes faster, "rnnt" is more accurate.
+    pub decoder: String,
+    /// End-to-end models also restore punctuation and letter case.
+    #[serde(default)]
+    pub e2e: bool,
+    #[serde(default)]
+    pub downloaded: bool,
+    /// Checkpoint size on disk; `None` until the model is downloaded on first use.
+    #[serde(default)]
+    pub size_bytes: Option<u64>,
+}
+
+/// Assumed for sidecars that do not advertise models in `ready`: the GigaAM v3 variants.
+pub(crate) const FALLBACK_MODELS: &[&str] = &["v3_ctc", "v3_rnnt", "v3_e2e_ctc", "v3_e2e_rnnt"];
+
+/// The model names a sidecar reported, or the fallback when it reported none.
+pub(crate) fn advertised_models(reported: &[String]) -> Vec<String> {
+    if reported.is_empty() {
+        FALLBACK_MODELS.iter().map(|m| m.to_string()).collect()
+    } else {
+        reported.to_vec()
+    }
+}
+
+/// Commands written to sidecar stdin, one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub(crate) enum SidecarCommand {
+    Init {
+        protocol_version: u32,
+        capabilities: Vec<String>,
+    },
+    StartRecording,
+    StopAndTranscribe,
+    CancelCurrent,
+    SetConfig {
+        config: SidecarConfig,
+    },
+    Healthcheck,
+    Shutdown,
+    PreloadModel,
+    /// Transcribe a WAV/FLAC/OGG file; the result arrives like a dictation result.
+    TranscribeFile {
+        path: String,
+    },
+    ListInputDevices,
+    ListModels,
+}
+
+impl SidecarCommand {
+    /// Command names this build can send; advertised to the sidecar in `init`.
+    pub const NAMES: &'static [&'static str] = &[
+        "init",
+        "start_recording",
+        "stop_and_transcribe",
+        "cancel_current",
+        "set_config",
+        "healthcheck",
+        "shutdown",
+        "preload_model",
+        "transcribe_file",
+        "list_input_devices",
+        "list_models",
+    ];
+
+    pub fn init() -> Self {
+        Self::Init {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: Self::NAMES.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Init { .. } => "init",
+            Self::StartRecording => "start_recording",
+            Self::StopAndTranscribe => "stop_and_transcribe",
+            Self::CancelCurrent => "cancel_current",
+            Self::SetConfig { .. } => "set_config",
+            Self::Healthcheck => "healthcheck",
+            Self::Shutdown => "shutdown",
+            Self::PreloadModel => "preload_model",
+            Self::TranscribeFile { .. } => "transcribe_file",
+            Self::ListInputDevices => "list_input_devices",
+            Self::ListModels => "list_models",
+        }
+    }
+
+    /// Serializes the command with its request `id`, which the sidecar echoes in replies.
+    pub fn to_line(&self, id: u64) -> Result<String, String> {
+        let mut value = serde_json::to_value(self)
+            .map_err(|e| format!("failed to serialize sidecar command: {e}"))?;
+        if let Value::Object(map) = &mut value {
+            map.insert("id".to_string(), Value::from(id));
+        }
+        Ok(format!("{value}\n"))
+    }
+}
+
+/// Events read from sidecar stdout and forwarded to the frontend as `asr_event`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum SidecarEvent {
+    Ready {
+        device: String,
+        model: String,
+        /// Missing on sidecars that predate the handshake.
+        #[serde(default)]
+        protocol_version: u32,
+        #[serde(default)]
+        sidecar_version: String,
+        #[serde(default)]
+        commands: Vec<String>,
+        /// Language codes the model transcribes; empty on sidecars that predate the field.
+        #[serde(default)]
+        languages: Vec<String>,
+        /// Model names `set_config` accepts; empty on sidecars that predate the field.
+        #[serde(default)]
+        models: Vec<String>,
+    },
+    RecordingStarted,
+    RecordingStopped,
+    PartialTranscript {
+        text: String,
+    },
+    FinalTranscript {
+        text: String,
+    },
+    JobCancelled,
+    Error {
+        message: String,
+    },
+    Metrics {
+        latency_ms: u64,
+        device: String,
+        model: String,
+    },
+    SidecarIdleRestart,
+    ModelLoading {
+        model: String,
+    },
+    ModelLoaded {
+        model: String,
+        device: String,
+        load_ms: u64,
+    },
+    /// One window of a transcription with its position in the audio.
+    TranscriptSegment {
+        index: u32,
+        start_ms: u64,
+        end_ms: u64,
+        text: String,
+    },
+    /// The sidecar's VAD ended the recording after `silence_ms` of silence; transcription follows.
+    AutoStopped {
+        silence_ms: u64,
+    },
+    /// The recording held no speech, so the model was not run.
+    NoSpeech,
+    /// Reply to `list_input_devices`.
+    InputDevices {
+        devices: Vec<InputDevice>,
+        #[serde(default)]
+        default_id: Option<u32>,
+    },
+    /// Reply to `list_models`; `current` is the configured model.
+    Models {
+        models: Vec<ModelInfo>,
+        current: String,
+    },
+    /// The configured microphone could not be used; recording continues on the default one.
+    InputDeviceFallback {
+        requested: String,
+        message: String,
+    },
+    /// A recording was stored under the configured recordings dir before being transcribed.
+    RecordingSaved {
+        path: String,
+        duration_ms: u64,
+    },
+    /// Microphone level while recording, about 20 times a second; both values are 0..1.
+    AudioLevel {
+        rms: f32,
+        peak: f32,
+    },
+    /// Progress of a long or file transcription; `progress` is the finished fraction of `stage`.
+    TranscriptionProgress {
+        stage: String,
+        progress: f32,
+    },
+}
+
+impl SidecarEvent {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::Error {
+            message: message.into(),
+        }
+    }
+}
+
+/// Event plus the id of the command it answers; `None` for unsolicited events.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SidecarMessage {
+    pub id: Option<u64>,
+    pub event: SidecarEvent,
+}
+
+/// Request ids and the callers waiting on a reply to one of them.
+#[derive(Default)]
+pub(crate) struct PendingRequests {
+    next_id: AtomicU64,
+    waiters: Mutex<HashMap<u64, Sender<SidecarEvent>>>,
+}
+
+impl PendingRequests {
+    pub fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    pub fn register(&self, id: u64) -> Receiver<SidecarEvent> {
+        let (tx, rx) = mpsc::channel();
+        if let Ok(mut waiters) = self.waiters.lock() {
+            waiters.insert(id, tx);
+        }
+        rx
+    }
+
+    /// Hands the event to whoever awaits `id`; gives it back if nobody does.
+    pub fn resolve(&self, id: u64, event: SidecarEvent) -> Option<SidecarEvent> {
+        let waiter = self.waiters.lock().ok()?.remove(&id);
+        match waiter {
+            Some(tx) => tx.send(event).err().map(|e| e.0),
+            None => Some(event),
+        }
+    }
+
+    pub fn forget(&self, id: u64) {
+        if let Ok(mut waiters) = self.waiters.lock() {
+            waiters.remove(&id);
+        }
+    }
+
+    /// Drops all waiters so they fail fast, e.g. when the sidecar disconnects.
+    pub fn clear(&self) {
+        if let Ok(mut waiters) = self.waiters.lock() {
+            waiters.clear();
+        }
+    }
+}
+
+/// Outcome of the `init`/`ready` handshake.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SidecarCompatibility {
+    /// No `ready` seen yet for the current process.
+    Unknown,
+    Compatible,
+    /// Same protocol, but some commands are unavailable and get refused one by one.
+    Degraded {
+        missing: Vec<String>,
+    },
+    /// Different protocol; everything except `init`/`shutdown` is refused.
+    Incompatible {
+        reason: String,
+    },
+}
+
+impl SidecarCompatibility {
+    pub fn evaluate(protocol_version: u32, commands: &[String]) -> Self {
+        if protocol_version == 0 {
+            return Self::Incompatible {
+                reason: format!(
+                    "ASR sidecar is outdated (no protocol version, app expects v{PROTOCOL_VERSION}). Reinstall the app."
+                ),
+            };
+        }
+        if protocol_version != PROTOCOL_VERSION {
+            return Self::Incompatible {
+                reason: format!(
+                    "ASR sidecar speaks protocol v{protocol_version}, app expects v{PROTOCOL_VERSION}. Reinstall the app."
+                ),
+            };
+        }
+
+        let missing: Vec<String> = SidecarCommand::NAMES
+            .iter()
+            .filter(|name| !commands.iter().any(|c| c == *name))
+            .map(|name| name.to_string())
+            .collect();
+        if missing.is_empty() {
+            Self::Compatible
+        } else {
+            Self::Degraded { missing }
+        }
+    }
+
+    pub fn check_command(&self, command: &SidecarCommand) -> Result<(), String> {
+        if matches!(
+            command,
+            SidecarCommand::Init { .. } | SidecarCommand::Shutdown
+        ) {
+            return Ok(());
+        }
+
+        match self {
+            Self::Unknown | Self::Compatible => Ok(()),
+            Self::Degraded { missing } => {
+                let name = command.name();
+                if missing.iter().any(|m| m == name) {
+                    Err(format!(
+                        "ASR sidecar does not support '{name}'; update the app"
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            Self::Incompatible { reason } => Err(reason.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ProtocolError {
+    /// Line is not a JSON object (stray library output, partial writes).
+    InvalidJson(String),
+    /// JSON object without a string `event` tag.
+    MissingEvent,
+    /// Event name this build does not know about.
+    UnknownEvent(String),
+    /// Known event whose fields do not match the contract.
+    MalformedEvent { event: String, message: String },
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidJson(e) => write!(f, "invalid JSON: {e}"),
+            Self::MissingEvent => write!(f, "missing 'event' field"),
+            Self::UnknownEvent(name) => write!(f, "unknown event '{name}'"),
+            Self::MalformedEvent { event, message } => {
+                write!(f, "malformed '{event}' event: {message}")
+            }
+        }
+    }
+}
+
+pub(crate) fn parse_event(raw: &str) -> Result<SidecarMessage, ProtocolError> {
+    let value: Value =
+        serde_json::from_str(raw).map_err(|e| ProtocolError::InvalidJson(e.to_string()))?;
+    if !value.is_object() {
+        return Err(ProtocolError::InvalidJson("expected an object".to_string()));
+    }
+
+    let name = value
+        .get("event")
+        .and_then(Value::as_str)
+        .ok_or(ProtocolError::MissingEvent)?
+        .to_string();
+    let id = value.get("id").and_then(Value::as_u64);
+    let event = serde_json::from_value(value).map_err(|e| {
+        let message = e.to_string();
+        // The known names come from `SidecarEvent` itself, so new variants need no extra list.
+        if message.starts_with(&format!("unknown variant `{name}`")) {
+            ProtocolError::UnknownEvent(name.clone())
+        } else {
+            ProtocolError::MalformedEvent {
+                event: name.clone(),
+                message,
+            }
+        }
+    })?;
+    Ok(SidecarMessage { id, event })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_event, PendingRequests, ProtocolError, SidecarCommand, SidecarCompatibility,
+        SidecarConfig, SidecarEvent, SidecarMessage, PROTOCOL_VERSION,
+    };
+
+    fn sample_config() -> SidecarConfig {
+        SidecarConfig {
+            language_mode: "ru".to_string(),
+            popup_timeout_sec: 10,
+            model_keepalive_min: 5,
+            streaming_partials: true,
+            vad_silence_ms: 1_500,
+            vad_threshold_dbfs: -45.0,
+            input_device: None,
+            recordings_dir: None,
+            model: "v3_ctc".to_string(),
+        }
+    }
+
+    #[test]
+    fn serializes_tagged_commands() {
+        let line = SidecarCommand::StartRecording.to_line(7).unwrap();
+        assert_eq!(line, "{\"command\":\"start_recording\",\"id\":7}\n");
+
+        let config = SidecarCommand::SetConfig {
+            config: sample_config(),
+        };
+        let value: serde_json::Value = serde_json::to_value(&config).unwrap();
+        assert_eq!(value["command"], "set_config");
+        assert_eq!(value["config"]["model_keepalive_min"], 5);
+        assert_eq!(value["config"]["vad_silence_ms"], 1_500);
+        assert_eq!(value["config"]["model"], "v3_ctc");
+    }
+
+    #[test]
+    fn advertised_names_match_every_command() {
+        let commands = [
+            SidecarCommand::init(),
+            SidecarCommand::StartRecording,
+            SidecarCommand::StopAndTranscribe,
+            SidecarCommand::CancelCurrent,
+            SidecarCommand::SetConfig {
+                config: sample_config(),
+            },
+            SidecarCommand::Healthcheck,
+            SidecarCommand::Shutdown,
+            SidecarCommand::PreloadModel,
+            SidecarCommand::TranscribeFile {
+                path: "/tmp/a.wav".to_string(),
+            },
+            SidecarCommand::ListInputDevices,
+            SidecarCommand::ListModels,
+        ];
+        for command in &commands {
+            // Fails to compile when a variant is added, as a reminder to list it above.
+            match command {
+                SidecarCommand::Init { .. }
+                | SidecarCommand::StartRecording
+                | SidecarCommand::StopAndTranscribe
+                | SidecarCommand::CancelCurrent
+                | SidecarCommand::SetConfig { .. }
+                | SidecarCommand::Healthcheck
+                | SidecarCommand::Shutdown
+                | SidecarCommand::PreloadModel
+                | SidecarCommand::TranscribeFile { .. }
+                | SidecarCommand::ListInputDevices
+                | SidecarCommand::ListModels => {}
+            }
+            let value = serde_json::to_value(command).unwrap();
+            assert_eq!(value["command"], command.name());
+        }
+        let names: Vec<&str> = commands.iter().map(SidecarCommand::name).collect();
+        assert_eq!(names, SidecarCommand::NAMES);
+    }
+
+    #[test]
+    fn parses_known_events() {
+        assert_eq!(
+            parse_event(r#"{"event":"final_transcript","text":"привет","id":3}"#),
+            Ok(SidecarMessage {
+                id: Some(3),
+                event: SidecarEvent::FinalTranscript {
+                    text: "привет".to_string()
+                }
+            })
+        );
+        assert_eq!(
+            parse_event(r#"{"event":"job_cancelled"}"#),
+            Ok(SidecarMessage {
+                id: None,
+                event: SidecarEvent::JobCancelled
+            })
+        );
+        assert_eq!(
+            parse_event(r#"{"event":"transcription_progress","stage":"decoding","progress":0.5}"#),
+            Ok(SidecarMessage {
+                id: None,
+                event: SidecarEvent::TranscriptionProgress {
+                    stage: "decoding".to_string(),
+                    progress: 0.5
+                }
+            })
+        );
+        assert_eq!(
+            parse_event(r#"{"event":"auto_stopped","silence_ms":1500,"id":9}"#),
+            Ok(SidecarMessage {
+                id: Some(9),
+                event: SidecarEvent::AutoStopped { silence_ms: 1500 }
+            })
+        );
+        assert_eq!(
+            parse_event(r#"{"event":"no_speech","id":9}"#),
+            Ok(SidecarMessage {
+                id: Some(9),
+                event: SidecarEvent::NoSpeech
+            })
+        );
+        assert_eq!(
+            parse_event(r#"{"event":"audio_level","rms":0.25,"peak":0.5}"#),
+            Ok(SidecarMessage {
+                id: None,
+                event: SidecarEvent::AudioLevel {
+                    rms: 0.25,
+                    peak: 0.5
+                }
+            })
+        );
+
+        let message = parse_event(
+            r#"{"event":"input_devices","id":4,"default_id":1,"devices":[{"id":1,"name":"USB Headset","sample_rate":16000,"channels":1,"is_default":true}]}"#,
+        )
+        .unwrap();
+        match message.event {
+            SidecarEvent::InputDevices {
+                devices,
+                default_id,
+            } => {
+                assert_eq!(default_id, Some(1));
+                assert_eq!(devices[0].name, "USB Headset");
+                assert!(devices[0].is_default);
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+
+        let message = parse_event(
+            r#"{"event":"models","id":5,"current":"v3_e2e_rnnt","models":[{"name":"v3_ctc","decoder":"ctc","e2e":false,"downloaded":true,"size_bytes":10},{"name":"v3_e2e_rnnt","decoder":"rnnt","e2e":true,"downloaded":false,"size_bytes":null}]}"#,
+        )
+        .unwrap();
+        match message.event {
+            SidecarEvent::Models { models, current } => {
+                assert_eq!(current, "v3_e2e_rnnt");
+                assert_eq!(models[0].size_bytes, Some(10));
+                assert!(models[1].e2e && models[1].size_bytes.is_none());
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_and_malformed_events() {
+        assert_eq!(
+            parse_event(r#"{"event":"surprise"}"#),
+            Err(ProtocolError::UnknownEvent("surprise".to_string()))
+        );
+        assert!(matches!(
+            parse_event(r#"{"event":"final_transcript"}"#),
+            Err(ProtocolError::MalformedEvent { .. })
+        ));
+        assert!(matches!(
+            parse_event(r#"{"event":"models","current":"v3_ctc","models":[{"name":"v3_ctc"}]}"#),
+            Err(ProtocolError::MalformedEvent { .. })
+        ));
+        assert_eq!(
+            parse_event(r#"{"text":"x"}"#),
+            Err(ProtocolError::MissingEvent)
+        );
+        assert!(matches!(
+            parse_event("loading weights..."),
+            Err(ProtocolError::InvalidJson(_))
+        ));
+    }
+
+    #[test]
+    fn init_advertises_every_command() {
+        let value = serde_json::to_value(SidecarCommand::init()).unwrap();
+        assert_eq!(value["command"], "init");
+        assert_eq!(value["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(
+            value["capabilities"].as_array().unwrap().len(),
+            SidecarCommand::NAMES.len()
+        );
+    }
+
+    #[test]
+    fn legacy_ready_is_incompatible() {
+        let event = parse_event(r#"{"event":"ready","device":"cpu","model":"v3_e2e_rnnt"}"#);
+        let Ok(SidecarMessage {
+            event:
+                SidecarEvent::Ready {
+                    protocol_version,
+                    commands,
+                    ..
+                },
+            ..
+        }) = event
+        else {
+            panic!("expected ready event");
+        };
+
+        let compat = SidecarCompatibility::evaluate(protocol_version, &commands);
+        assert!(matches!(compat, SidecarCompatibility::Incompatible { .. }));
+        assert!(compat
+            .check_command(&SidecarCommand::StartRecording)
+            .is_err());
+        assert!(compat.check_command(&SidecarCommand::Shutdown).is_ok());
+    }
+
+    #[test]
+    fn missing_commands_degrade_only_those_commands() {
+        let commands: Vec<String> = SidecarCommand::NAMES
+            .iter()
+            .filter(|name| **name != "healthcheck")
+            .map(|name| name.to_string())
+            .collect();
+
+        let compat = SidecarCompatibility::evaluate(PROTOCOL_VERSION, &commands);
+        assert_eq!(
+            compat,
+            SidecarCompatibility::Degraded {
+                missing: vec!["healthcheck".to_string()]
+            }
+        );
+        assert!(compat.check_command(&SidecarCommand::Healthcheck).is_err());
+        assert!(compat
+            .check_command(&SidecarCommand::StartRecording)
+            .is_ok());
+    }
+
+    #[test]
+    fn pending_requests_route_replies_by_id() {
+        let pending = PendingRequests::default();
+        let first = pending.next_id();
+        let second = pending.next_id();
+        assert!(second > first);
+
+        let rx = pending.register(second);
+        assert_eq!(
+            pending.resolve(first, SidecarEvent::JobCancelled),
+            Some(SidecarEvent::JobCancelled)
+        );
+        assert_eq!(
+            pending.resolve(second, SidecarEvent::RecordingStarted),
+            None
+        );
+        assert_eq!(rx.try_recv(), Ok(SidecarEvent::RecordingStarted));
+
+        let rx = pending.register(first);
+        pending.clear();
+        assert!(rx.recv().is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// In hybrid mode, holding the hotkey at least this long acts as push-to-talk.
+pub(crate) const HYBRID_HOLD_THRESHOLD: Duration = Duration::from_millis(400);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RecordingMode {
+    /// Record while the hotkey is held, transcribe on release.
+    #[default]
+    Hold,
+    /// First press starts recording, second press stops it.
+    Toggle,
+    /// Short tap toggles, long hold acts like `Hold`.
+    Hybrid,
+}
+
+impl RecordingMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Hold => Self::Toggle,
+            Self::Toggle => Self::Hybrid,
+            Self::Hybrid => Self::Hold,
+        }
+    }
+}
+
+/// Energy-based voice activity detection that ends a recording after trailing silence.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct VadSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Silence after speech that stops the recording.
+    #[serde(default = "default_vad_silence_ms")]
+    pub vad_silence_ms: u64,
+    /// Blocks quieter than this count as silence.
+    #[serde(default = "default_vad_threshold_dbfs")]
+    pub threshold_dbfs: f32,
+}
+
+pub(crate) const VAD_SILENCE_MS_RANGE: RangeInclusive<u64> = 300..=10_000;
+pub(crate) const VAD_THRESHOLD_DBFS_RANGE: RangeInclusive<f32> = -80.0..=-10.0;
+
+fn default_vad_silence_ms() -> u64 {
+    1_500
+}
+
+fn default_vad_threshold_dbfs() -> f32 {
+    -45.0
+}
+
+impl Default for VadSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            vad_silence_ms: default_vad_silence_ms(),
+            threshold_dbfs: default_vad_threshold_dbfs(),
+        }
+    }
+}
+
+impl VadSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !VAD_SILENCE_MS_RANGE.contains(&self.vad_silence_ms) {
+            return Err(format!(
+                "vad silence must be between {} and {} ms",
+                VAD_SILENCE_MS_RANGE.start(),
+                VAD_SILENCE_MS_RANGE.end()
+            ));
+        }
+        if !VAD_THRESHOLD_DBFS_RANGE.contains(&self.threshold_dbfs) {
+            return Err(format!(
+                "vad threshold must be between {} and {} dBFS",
+                VAD_THRESHOLD_DBFS_RANGE.start(),
+                VAD_THRESHOLD_DBFS_RANGE.end()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Trailing silence the sidecar should stop on; 0 when VAD is off.
+    pub fn sidecar_silence_ms(&self) -> u64 {
+        if self.enabled {
+            self.vad_silence_ms
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HotkeyAction {
+    StartRecording,
+    StopAndTranscribe,
+    Nothing,
+}
+
+/// Tracks one physical hotkey across press/release so the modes can tell taps from holds.
+#[derive(Debug, Default)]
+pub(crate) struct HotkeyGesture {
+    key_down: bool,
+    pressed_at: Option<Instant>,
+    press_started_recording: bool,
+}
+
+impl HotkeyGesture {
+    pub fn press(&mut self, mode: RecordingMode, recording: bool, now: Instant) -> HotkeyAction {
+        // Key auto-repeat delivers extra presses while held; only the first one counts.
+        if self.key_down {
+            return HotkeyAction::Nothing;
+        }
+        self.key_down = true;
+        self.pressed_at = Some(now);
+        self.press_started_recording = false;
+
+        match mode {
+            RecordingMode::Hold if recording => HotkeyAction::Nothing,
+            RecordingMode::Toggle | RecordingMode::Hybrid if recording => {
+                HotkeyAction::StopAndTranscribe
+            }
+            _ => {
+                self.press_started_recording = true;
+                HotkeyAction::StartRecording
+            }
+        }
+    }
+
+    pub fn release(&mut self, mode: RecordingMode, recording: bool, now: Instant) -> HotkeyAction {
+        let pressed_at = self.pressed_at.take();
+        let started_recording = std::mem::take(&mut self.press_started_recording);
+        self.key_down = false;
+
+        if !recording {
+            return HotkeyAction::Nothing;
+        }
+
+        match mode {
+            RecordingMode::Hold => HotkeyAction::StopAndTranscribe,
+            RecordingMode::Toggle => HotkeyAction::Nothing,
+            RecordingMode::Hybrid => {
+                let held = pressed_at
+                    .map(|at| now.saturating_duration_since(at))
+                    .unwrap_or_default();
+                if started_recording && held >= HYBRID_HOLD_THRESHOLD {
+                    HotkeyAction::StopAndTranscribe
+                } else {
+                    HotkeyAction::Nothing
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{HotkeyAction, HotkeyGesture, RecordingMode, VadSettings, HYBRID_HOLD_THRESHOLD};
+
+    #[test]
+    fn hold_mode_records_while_pressed() {
+        let mut gesture = HotkeyGesture::default();
+        let t0 = Instant::now();
+        assert_eq!(
+            gesture.press(RecordingMode::Hold, false, t0),
+            HotkeyAction::StartRecording
+        );
+        assert_eq!(
+            gesture.release(RecordingMode::Hold, true, t0),
+            HotkeyAction::StopAndTranscribe
+        );
+    }
+
+    #[test]
+    fn toggle_mode_ignores_release_and_auto_repeat() {
+        let mut gesture = HotkeyGesture::default();
+        let t0 = Instant::now();
+        let mode = RecordingMode::Toggle;
+        assert_eq!(gesture.press(mode, false, t0), HotkeyAction::StartRecording);
+        assert_eq!(gesture.press(mode, true, t0), HotkeyAction::Nothing);
+        assert_eq!(gesture.release(mode, true, t0), HotkeyAction::Nothing);
+        assert_eq!(
+            gesture.press(mode, true, t0),
+            HotkeyAction::StopAndTranscribe
+        );
+        assert_eq!(gesture.release(mode, false, t0), HotkeyAction::Nothing);
+    }
+
+    #[test]
+    fn hybrid_mode_tells_taps_from_holds() {
+        let mode = RecordingMode::Hybrid;
+        let t0 = Instant::now();
+        let short = t0 + Duration::from_millis(120);
+        let long = t0 + HYBRID_HOLD_THRESHOLD;
+
+        let mut gesture = HotkeyGesture::default();
+        assert_eq!(gesture.press(mode, false, t0), HotkeyAction::StartRecording);
+        assert_eq!(
+            gesture.release(mode, true, long),
+            HotkeyAction::StopAndTranscribe
+        );
+
+        assert_eq!(gesture.press(mode, false, t0), HotkeyAction::StartRecording);
+        assert_eq!(gesture.release(mode, true, short), HotkeyAction::Nothing);
+        assert_eq!(
+            gesture.press(mode, true, long),
+            HotkeyAction::StopAndTranscribe
+        );
+        assert_eq!(gesture.release(mode, false, long), HotkeyAction::Nothing);
+    }
+
+    #[test]
+    fn toggle_mode_starts_again_after_auto_stop() {
+        let mut gesture = HotkeyGesture::default();
+        let t0 = Instant::now();
+        let mode = RecordingMode::Toggle;
+        assert_eq!(gesture.press(mode, false, t0), HotkeyAction::StartRecording);
+        assert_eq!(gesture.release(mode, true, t0), HotkeyAction::Nothing);
+        // `auto_stopped` cleared the recording flag; the next press starts a new recording.
+        assert_eq!(gesture.press(mode, false, t0), HotkeyAction::StartRecording);
+    }
+
+    #[test]
+    fn vad_settings_validation() {
+        let vad = VadSettings::default();
+        assert!(vad.validate().is_ok());
+        assert_eq!(vad.sidecar_silence_ms(), 0);
+
+        let enabled = VadSettings {
+            enabled: true,
+            ..vad
+        };
+        assert_eq!(enabled.sidecar_silence_ms(), 1_500);
+        assert!(VadSettings {
+            vad_silence_ms: 50,
+            ..enabled
+        }
+        .validate()
+        .is_err());
+        assert!(VadSettings {
+            threshold_dbfs: 0.0,
+            ..enabled
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+pub(crate) const RECORDINGS_DIR_NAME: &str = "recordings";
+/// The sidecar stores recordings as FLAC, which `transcribe_file` reads back.
+pub(crate) const RECORDING_EXTENSION: &str = "flac";
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Limits for stored recordings; `None` means unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RetentionPolicy {
+    pub max_bytes: Option<u64>,
+    pub max_age: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    /// Builds a policy from settings, where 0 turns a limit off.
+    pub fn from_limits(max_mb: u64, max_age_days: u64) -> Self {
+        Self {
+            max_bytes: (max_mb > 0).then(|| max_mb.saturating_mul(BYTES_PER_MB)),
+            max_age: (max_age_days > 0)
+                .then(|| Duration::from_secs(max_age_days.saturating_mul(SECS_PER_DAY))),
+        }
+    }
+}
+
+/// What a retention pass did. A file that cannot be deleted is reported and skipped, so one
+/// locked recording does not stop the rest of the pass.
+#[derive(Debug, Default)]
+pub(crate) struct RetentionOutcome {
+    pub removed: Vec<PathBuf>,
+    /// How many of the removed recordings a history entry still linked to.
+    pub removed_linked: usize,
+    pub failed: Vec<String>,
+}
+
+/// Deletes recordings older than the age limit, then the oldest ones until the rest fit the
+/// size limit. Recordings no history entry links to go first; `keep`, the recording that was
+/// just saved and is not linked yet, is never deleted. Only recording files are touched.
+pub(crate) fn apply_retention(
+    dir: &Path,
+    policy: RetentionPolicy,
+    now: SystemTime,
+    keep: Option<&Path>,
+    is_linked: impl Fn(&Path) -> bool,
+) -> Result<RetentionOutcome, String> {
+    let mut outcome = RetentionOutcome::default();
+    if !dir.exists() {
+        return Ok(outcome);
+    }
+
+    let mut recordings: Vec<(PathBuf, SystemTime, u64, bool)> = Vec::new();
+    let entries = fs::read_dir(dir).map_err(|e| format!("failed to read recordings dir: {e}"))?;
+    for entry in entries.map_while(Result::ok) {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(RECORDING_EXTENSION) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let modified = metadata.modified().unwrap_or(now);
+        let linked = is_linked(&path);
+        recordings.push((path, modified, metadata.len(), linked));
+    }
+    // Unlinked first, oldest first within each group.
+    recordings.sort_by_key(|(_, modified, _, linked)| (*linked, *modified));
+
+    let mut total: u64 = recordings.iter().map(|(_, _, size, _)| size).sum();
+    for (path, modified, size, linked) in recordings {
+        if keep == Some(path.as_path()) {
+            continue;
+        }
+        let too_old = policy
+            .max_age
+            .is_some_and(|max_age| now.duration_since(modified).unwrap_or_default() > max_age);
+        let too_big = policy.max_bytes.is_some_and(|max_bytes| total > max_bytes);
+        if !too_old && !too_big {
+            continue;
+        }
+        if let Err(e) = fs::remove_file(&path) {
+            outcome.failed.push(format!(
+                "failed to delete recording {}: {e}",
+                path.display()
+            ));
+            continue;
+        }
+        total -= size;
+        outcome.removed_linked += usize::from(linked);
+        outcome.removed.push(path);
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    use super::{apply_retention, RetentionPolicy};
+
+    fn temp_recordings(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "sber-whisper-recordings-{}-{name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_recording(dir: &Path, name: &str, size: usize, modified: SystemTime) {
+        let path = dir.join(name);
+        std::fs::write(&path, vec![0u8; size]).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn removes_old_then_oldest_recordings_over_the_size_limit() {
+        let dir = temp_recordings("retention");
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        write_recording(&dir, "ancient.flac", 10, now - 40 * day);
+        write_recording(&dir, "old.flac", 600_000, now - 3 * day);
+        write_recording(&dir, "recent.flac", 600_000, now - day);
+        write_recording(&dir, "today.flac", 600_000, now);
+        write_recording(&dir, "notes.txt", 5_000_000, now - 90 * day);
+
+        let policy = RetentionPolicy::from_limits(1, 30);
+        let outcome = apply_retention(&dir, policy, now, None, |_| false).unwrap();
+        let names: Vec<_> = outcome
+            .removed
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["ancient.flac", "old.flac", "recent.flac"]);
+        assert!(dir.join("today.flac").exists());
+        assert!(dir.join("notes.txt").exists());
+
+        let unlimited = RetentionPolicy::from_limits(0, 0);
+        assert!(
+            apply_retention(&dir, unlimited, now + 400 * day, None, |_| false)
+                .unwrap()
+                .removed
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn keeps_the_new_recording_and_linked_ones_as_long_as_possible() {
+        let dir = temp_recordings("linked");
+        let now = SystemTime::now();
+        let minute = Duration::from_secs(60);
+        write_recording(&dir, "linked.flac", 600_000, now - 3 * minute);
+        write_recording(&dir, "unlinked.flac", 600_000, now - 2 * minute);
+        write_recording(&dir, "new.flac", 2_000_000, now);
+
+        // The new recording alone is over the limit, yet it survives, and the unlinked one goes
+        // before the older linked one.
+        let policy = RetentionPolicy::from_limits(2, 0);
+        let new = dir.join("new.flac");
+        let is_linked = |path: &Path| path.ends_with("linked.flac");
+        let outcome = apply_retention(&dir, policy, now, Some(&new), is_linked).unwrap();
+        assert_eq!(
+            outcome.removed,
+            vec![dir.join("unlinked.flac"), dir.join("linked.flac")]
+        );
+        assert_eq!(outcome.removed_linked, 1);
+        assert!(outcome.failed.is_empty());
+        assert!(new.exists());
+    }
+}
@@ -0,0 +1,238 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+pub(crate) const REPLACEMENTS_FILE_NAME: &str = "replacements.json";
+/// Bumped whenever the rules file format changes; older files are read as they are.
+pub(crate) const REPLACEMENTS_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MatchKind {
+    /// Exact, case-sensitive substring.
+    #[default]
+    Literal,
+    /// Substring in any letter case.
+    CaseInsensitive,
+    /// Whole words only, in any letter case, so "гига" does not touch "гигабайт".
+    WholeWord,
+    /// Regular expression; the replacement may use `$1` or `${name}` groups.
+    Regex,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ReplacementRule {
+    pub pattern: String,
+    pub replacement: String,
+    #[serde(default)]
+    pub kind: MatchKind,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RulesFile {
+    version: u32,
+    #[serde(default)]
+    rules: Vec<ReplacementRule>,
+}
+
+/// Compiled rules, applied in list order: each rule sees the output of the ones before it.
+#[derive(Debug, Default)]
+pub(crate) struct Replacer {
+    rules: Vec<(Regex, String, bool)>,
+}
+
+impl Replacer {
+    pub fn new(rules: &[ReplacementRule]) -> Result<Self, String> {
+        let mut compiled = Vec::new();
+        for (i, rule) in rules.iter().enumerate() {
+            if !rule.enabled {
+                continue;
+            }
+            if rule.pattern.is_empty() {
+                return Err(format!("rule {} has an empty pattern", i + 1));
+            }
+            let (source, case_insensitive) = match rule.kind {
+                MatchKind::Literal => (regex::escape(&rule.pattern), false),
+                MatchKind::CaseInsensitive => (regex::escape(&rule.pattern), true),
+                MatchKind::WholeWord => (whole_word(&rule.pattern), true),
+                MatchKind::Regex => (rule.pattern.clone(), false),
+            };
+            let regex = RegexBuilder::new(&source)
+                .case_insensitive(case_insensitive)
+                .build()
+                .map_err(|e| format!("rule {} is not a valid pattern: {e}", i + 1))?;
+            compiled.push((
+                regex,
+                rule.replacement.clone(),
+                rule.kind == MatchKind::Regex,
+            ));
+        }
+        Ok(Self { rules: compiled })
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for (regex, replacement, expand) in &self.rules {
+            let replaced = if *expand {
+                regex.replace_all(&out, replacement.as_str())
+            } else {
+                regex.replace_all(&out, NoExpand(replacement))
+            };
+            out = replaced.into_owned();
+        }
+        out
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// `\b` only holds next to a word character, so an edge like the "#" of "C#" or the "+" of "+1"
+/// gets no boundary; otherwise such patterns would never match.
+fn whole_word(pattern: &str) -> String {
+    let starts_with_word = pattern.chars().next().is_some_and(is_word_char);
+    let ends_with_word = pattern.chars().last().is_some_and(is_word_char);
+    format!(
+        "{}{}{}",
+        if starts_with_word { r"\b" } else { "" },
+        regex::escape(pattern),
+        if ends_with_word { r"\b" } else { "" },
+    )
+}
+
+/// Reads the rules file; a missing file means no rules.
+pub(crate) fn load_rules(path: &Path) -> Result<Vec<ReplacementRule>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = fs::read_to_string(path).map_err(|e| format!("failed to read replacements: {e}"))?;
+    let file: RulesFile =
+        serde_json::from_str(&raw).map_err(|e| format!("failed to parse replacements: {e}"))?;
+    if file.version > REPLACEMENTS_VERSION {
+        return Err(format!(
+            "replacements file version {} is newer than supported version {REPLACEMENTS_VERSION}",
+            file.version
+        ));
+    }
+    Ok(file.rules)
+}
+
+/// Writes the rules through a temp file, so a crash never leaves a truncated file behind.
+pub(crate) fn save_rules(path: &Path, rules: &[ReplacementRule]) -> Result<(), String> {
+    let file = RulesFile {
+        version: REPLACEMENTS_VERSION,
+        rules: rules.to_vec(),
+    };
+    let json = serde_json::to_string_pretty(&file)
+        .map_err(|e| format!("failed to serialize replacements: {e}"))?;
+
+    let tmp_path = path.with_extension("json.tmp");
+    let mut tmp =
+        File::create(&tmp_path).map_err(|e| format!("failed to create replacements file: {e}"))?;
+    writeln!(tmp, "{json}").map_err(|e| format!("failed to write replacements: {e}"))?;
+    drop(tmp);
+    fs::rename(&tmp_path, path).map_err(|e| format!("failed to replace replacements file: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_rules, save_rules, MatchKind, ReplacementRule, Replacer};
+
+    fn rule(kind: MatchKind, pattern: &str, replacement: &str) -> ReplacementRule {
+        ReplacementRule {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            kind,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn applies_each_match_kind() {
+        let replacer = Replacer::new(&[
+            rule(MatchKind::Literal, "сбер", "Сбер"),
+            rule(MatchKind::CaseInsensitive, "гига ам", "GigaAM"),
+            rule(MatchKind::WholeWord, "апи", "API"),
+            rule(MatchKind::Regex, r"(\d+) процентов", "$1%"),
+        ])
+        .unwrap();
+        assert_eq!(
+            replacer.apply("сбер выпустил Гига АМ, апи растёт на 5 процентов, капитал тот же"),
+            "Сбер выпустил GigaAM, API растёт на 5%, капитал тот же"
+        );
+        // Literal kinds never expand `$` groups.
+        let literal = Replacer::new(&[rule(MatchKind::Literal, "доллар", "$1")]).unwrap();
+        assert_eq!(literal.apply("доллар"), "$1");
+    }
+
+    #[test]
+    fn whole_words_may_start_or_end_with_symbols() {
+        let replacer = Replacer::new(&[
+            rule(MatchKind::WholeWord, "си шарп", "C#"),
+            rule(MatchKind::WholeWord, "C#", "C# 12"),
+            rule(MatchKind::WholeWord, "+1", "плюс один"),
+        ])
+        .unwrap();
+        assert_eq!(
+            replacer.apply("пишу на си шарп, +1 к этому"),
+            "пишу на C# 12, плюс один к этому"
+        );
+        // The word edge still needs a boundary: "ABC#" is not "C#".
+        assert_eq!(replacer.apply("ABC# и C#"), "ABC# и C# 12");
+    }
+
+    #[test]
+    fn rules_apply_in_order() {
+        let first = rule(MatchKind::WholeWord, "джира", "Jira");
+        let second = rule(MatchKind::Literal, "Jira", "Jira Cloud");
+        let forward = Replacer::new(&[first.clone(), second.clone()]).unwrap();
+        assert_eq!(forward.apply("открой джира"), "открой Jira Cloud");
+
+        let backward = Replacer::new(&[second, first]).unwrap();
+        assert_eq!(backward.apply("открой джира"), "открой Jira");
+
+        let disabled = ReplacementRule {
+            enabled: false,
+            ..rule(MatchKind::Literal, "открой", "закрой")
+        };
+        assert_eq!(
+            Replacer::new(&[disabled]).unwrap().apply("открой"),
+            "открой"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_rules_and_round_trips_file() {
+        let err = Replacer::new(&[
+            rule(MatchKind::Literal, "a", "b"),
+            rule(MatchKind::Regex, "(", ""),
+        ])
+        .unwrap_err();
+        assert!(err.starts_with("rule 2"));
+        assert!(Replacer::new(&[rule(MatchKind::Literal, "", "x")]).is_err());
+
+        let dir =
+            std::env::temp_dir().join(format!("sber-whisper-replacements-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("replacements.json");
+        let _ = std::fs::remove_file(&path);
+        assert!(load_rules(&path).unwrap().is_empty());
+
+        let rules = vec![rule(MatchKind::WholeWord, "апи", "API")];
+        save_rules(&path, &rules).unwrap();
+        assert_eq!(load_rules(&path).unwrap(), rules);
+
+        std::fs::write(&path, r#"{"version": 99, "rules": []}"#).unwrap();
+        assert!(load_rules(&path).is_err());
+    }
+}
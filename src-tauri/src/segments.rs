@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// One timed piece of a transcript, as reported by `transcript_segment`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct TranscriptSegment {
+    pub index: u32,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// Assembles the segments of one transcription job, in index order.
+///
+/// Segments of a new request replace the previous job's segments, so after `final_transcript`
+/// the aggregator still holds the timing of the last transcript.
+#[derive(Debug, Default)]
+pub(crate) struct SegmentAggregator {
+    request_id: Option<u64>,
+    segments: BTreeMap<u32, TranscriptSegment>,
+}
+
+impl SegmentAggregator {
+    pub fn push(&mut self, request_id: Option<u64>, segment: TranscriptSegment) {
+        if request_id != self.request_id {
+            self.reset(request_id);
+        }
+        self.segments.insert(segment.index, segment);
+    }
+
+    /// Forgets the previous job's segments; `request_id` has none yet.
+    pub fn reset(&mut self, request_id: Option<u64>) {
+        self.request_id = request_id;
+        self.segments.clear();
+    }
+
+    /// Whether the aggregator holds the segments of `request_id`.
+    pub fn is_for(&self, request_id: Option<u64>) -> bool {
+        !self.segments.is_empty() && self.request_id == request_id
+    }
+
+    /// Segments in order, with start times clamped so they never overlap the previous one.
+    pub fn segments(&self) -> Vec<TranscriptSegment> {
+        let mut previous_end = 0;
+        self.segments
+            .values()
+            .map(|segment| {
+                let mut segment = segment.clone();
+                segment.start_ms = segment.start_ms.max(previous_end);
+                segment.end_ms = segment.end_ms.max(segment.start_ms);
+                previous_end = segment.end_ms;
+                segment
+            })
+            .collect()
+    }
+
+    pub fn text(&self) -> String {
+        self.segments
+            .values()
+            .map(|segment| segment.text.trim())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SegmentAggregator, TranscriptSegment};
+
+    fn segment(index: u32, start_ms: u64, end_ms: u64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            index,
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn assembles_out_of_order_segments_without_overlap() {
+        let mut aggregator = SegmentAggregator::default();
+        aggregator.push(Some(1), segment(1, 19_000, 39_000, "в офисе"));
+        aggregator.push(Some(1), segment(0, 0, 20_000, "встретимся утром"));
+        aggregator.push(Some(1), segment(2, 38_000, 41_000, ""));
+
+        assert_eq!(aggregator.text(), "встретимся утром в офисе");
+        let starts: Vec<u64> = aggregator.segments().iter().map(|s| s.start_ms).collect();
+        assert_eq!(starts, vec![0, 20_000, 39_000]);
+        assert!(aggregator.is_for(Some(1)));
+    }
+
+    #[test]
+    fn new_request_replaces_previous_job() {
+        let mut aggregator = SegmentAggregator::default();
+        aggregator.push(Some(1), segment(0, 0, 1_000, "старый"));
+        aggregator.push(Some(1), segment(0, 0, 1_000, "повтор"));
+        assert_eq!(aggregator.text(), "повтор");
+
+        aggregator.push(Some(2), segment(0, 0, 2_000, "новый"));
+        assert_eq!(aggregator.text(), "новый");
+        assert!(!aggregator.is_for(Some(1)));
+
+        aggregator.reset(Some(3));
+        assert!(aggregator.segments().is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use tauri_plugin_global_shortcut::Shortcut;
+
+use super::parse_shortcut;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ShortcutAction {
+    /// Start/stop dictation according to the recording mode.
+    Dictate,
+    CancelJob,
+    RepasteLast,
+    OpenSettings,
+    /// Cycle recording mode: hold -> toggle -> hybrid.
+    ToggleMode,
+    /// Put back what was on the clipboard before the last transcript.
+    RestoreClipboard,
+}
+
+impl ShortcutAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Dictate => "dictate",
+            Self::CancelJob => "cancel current job",
+            Self::RepasteLast => "re-paste last transcript",
+            Self::OpenSettings => "open settings",
+            Self::ToggleMode => "toggle recording mode",
+            Self::RestoreClipboard => "restore clipboard",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ShortcutBinding {
+    pub action: ShortcutAction,
+    /// Empty means the action is unbound.
+    pub hotkey: String,
+}
+
+/// Per-shortcut outcome of registering the keymap with the OS.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct ShortcutRegistration {
+    pub action: ShortcutAction,
+    pub hotkey: String,
+    pub error: Option<String>,
+}
+
+/// Rejects unparsable bindings and bindings that resolve to the same key combination.
+pub(crate) fn validate_keymap(bindings: &[ShortcutBinding]) -> Result<(), String> {
+    let mut seen: Vec<(Shortcut, &ShortcutBinding)> = Vec::with_capacity(bindings.len());
+    for binding in bindings {
+        let hotkey = binding.hotkey.trim();
+        if hotkey.is_empty() {
+            continue;
+        }
+        let shortcut = parse_shortcut(hotkey)?;
+        if let Some((_, other)) = seen.iter().find(|(s, _)| *s == shortcut) {
+            return Err(format!(
+                "shortcut '{hotkey}' for {} conflicts with '{}' for {}",
+                binding.action.label(),
+                other.hotkey.trim(),
+                other.action.label()
+            ));
+        }
+        seen.push((shortcut, binding));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_keymap, ShortcutAction, ShortcutBinding};
+
+    fn binding(action: ShortcutAction, hotkey: &str) -> ShortcutBinding {
+        ShortcutBinding {
+            action,
+            hotkey: hotkey.to_string(),
+        }
+    }
+
+    #[test]
+    fn accepts_distinct_and_unbound_shortcuts() {
+        let keymap = [
+            binding(ShortcutAction::Dictate, "Ctrl+G"),
+            binding(ShortcutAction::CancelJob, "Ctrl+Shift+G"),
+            binding(ShortcutAction::OpenSettings, ""),
+            binding(ShortcutAction::ToggleMode, ""),
+        ];
+        assert!(validate_keymap(&keymap).is_ok());
+    }
+
+    #[test]
+    fn detects_conflicts_after_normalization() {
+        let keymap = [
+            binding(ShortcutAction::Dictate, "Ctrl+G"),
+            binding(ShortcutAction::RepasteLast, "control+g"),
+        ];
+        let err = validate_keymap(&keymap).unwrap_err();
+        assert!(err.contains("re-paste last transcript"));
+        assert!(err.contains("dictate"));
+    }
+
+    #[test]
+    fn rejects_invalid_binding() {
+        let keymap = [binding(ShortcutAction::CancelJob, "not-a-hotkey")];
+        assert!(validate_keymap(&keymap).is_err());
+    }
+}
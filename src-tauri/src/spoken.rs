@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// What a spoken command does.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub(crate) enum CommandAction {
+    /// Puts `text` in place of the phrase; punctuation sticks to the word before it.
+    Insert { text: String },
+    /// Throws the whole dictation away. Only fires when the phrase is all that was said, so a
+    /// sentence like "нажми кнопку отмена" is kept.
+    Cancel,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SpokenCommand {
+    pub phrase: String,
+    #[serde(flatten)]
+    pub action: CommandAction,
+}
+
+/// Spoken commands are off by default: words like "точка" also occur in normal speech.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub(crate) struct SpokenCommandSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Added to the default table; a phrase that is already there replaces its action.
+    #[serde(default)]
+    pub custom: Vec<SpokenCommand>,
+}
+
+fn insert(phrase: &str, text: &str) -> SpokenCommand {
+    SpokenCommand {
+        phrase: phrase.to_string(),
+        action: CommandAction::Insert {
+            text: text.to_string(),
+        },
+    }
+}
+
+/// The built-in Russian command table.
+pub(crate) fn default_commands() -> Vec<SpokenCommand> {
+    vec![
+        insert("новая строка", "\n"),
+        insert("новый абзац", "\n\n"),
+        insert("точка", "."),
+        insert("запятая", ","),
+        insert("точка с запятой", ";"),
+        insert("двоеточие", ":"),
+        insert("вопросительный знак", "?"),
+        insert("восклицательный знак", "!"),
+        insert("многоточие", "…"),
+        insert("тире", "—"),
+        SpokenCommand {
+            phrase: "отмена".to_string(),
+            action: CommandAction::Cancel,
+        },
+    ]
+}
+
+/// Lower-cased words joined by single spaces, so "Новая  строка" finds "новая строка".
+fn phrase_key(phrase: &str) -> String {
+    phrase
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn phrase_pattern(key: &str) -> String {
+    key.split(' ')
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(r"\s+")
+}
+
+/// The compiled command table.
+#[derive(Debug)]
+pub(crate) struct SpokenCommands {
+    inserts: Option<Regex>,
+    texts: HashMap<String, String>,
+    cancel: Option<Regex>,
+}
+
+impl SpokenCommands {
+    /// Compiles `defaults` with the user's commands from `settings` on top.
+    pub fn new(
+        defaults: &[SpokenCommand],
+        settings: &SpokenCommandSettings,
+    ) -> Result<Self, String> {
+        for (i, command) in settings.custom.iter().enumerate() {
+            if phrase_key(&command.phrase).is_empty() {
+                return Err(format!("spoken command {} has an empty phrase", i + 1));
+            }
+        }
+
+        let mut actions: HashMap<String, CommandAction> = HashMap::new();
+        for command in defaults.iter().chain(&settings.custom).cloned() {
+            actions.insert(phrase_key(&command.phrase), command.action);
+        }
+
+        let mut texts = HashMap::new();
+        let mut cancels = Vec::new();
+        for (key, action) in actions {
+            match action {
+                CommandAction::Insert { text } => {
+                    texts.insert(key, text);
+                }
+                CommandAction::Cancel => cancels.push(key),
+            }
+        }
+
+        // Longest first, so "точка с запятой" wins over "точка".
+        let mut keys: Vec<&String> = texts.keys().collect();
+        keys.sort_by_key(|key| std::cmp::Reverse(key.chars().count()));
+        let alternation = |keys: &[&String]| {
+            keys.iter()
+                .map(|key| phrase_pattern(key))
+                .collect::<Vec<_>>()
+                .join("|")
+        };
+        let build = |source: String| {
+            RegexBuilder::new(&source)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("failed to compile spoken commands: {e}"))
+        };
+
+        let inserts = if keys.is_empty() {
+            None
+        } else {
+            Some(build(format!(r"\s*\b({})\b\s*", alternation(&keys)))?)
+        };
+        let cancel = if cancels.is_empty() {
+            None
+        } else {
+            let cancels: Vec<&String> = cancels.iter().collect();
+            Some(build(format!(r"^\W*({})\W*$", alternation(&cancels)))?)
+        };
+        Ok(Self {
+            inserts,
+            texts,
+            cancel,
+        })
+    }
+
+    /// Runs the commands on a transcript; `None` means the dictation was cancelled.
+    pub fn apply(&self, text: &str) -> Option<String> {
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.is_match(text))
+        {
+            return None;
+        }
+        let Some(inserts) = &self.inserts else {
+            return Some(text.to_string());
+        };
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for caps in inserts.captures_iter(text) {
+            let whole = caps.get(0).expect("group 0 always matches");
+            out.push_str(&text[last..whole.start()]);
+            last = whole.end();
+            let inserted = self
+                .texts
+                .get(&phrase_key(&caps[1]))
+                .map(String::as_str)
+                .unwrap_or_default();
+            if inserted.contains('\n') {
+                out.truncate(out.trim_end_matches(' ').len());
+                out.push_str(inserted);
+            } else if inserted.starts_with(['.', ',', '!', '?', ':', ';', '…', ')']) {
+                out.truncate(out.trim_end_matches(' ').len());
+                out.push_str(inserted);
+                out.push(' ');
+            } else {
+                if !out.is_empty() && !out.ends_with([' ', '\n']) {
+                    out.push(' ');
+                }
+                out.push_str(inserted);
+                out.push(' ');
+            }
+        }
+        out.push_str(&text[last..]);
+        Some(out.trim_matches(' ').to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        default_commands, CommandAction, SpokenCommand, SpokenCommandSettings, SpokenCommands,
+    };
+
+    fn defaults() -> SpokenCommands {
+        SpokenCommands::new(&default_commands(), &SpokenCommandSettings::default()).unwrap()
+    }
+
+    #[test]
+    fn default_table() {
+        let commands = defaults();
+        let cases = [
+            (
+                "привет запятая как дела вопросительный знак",
+                "привет, как дела?",
+            ),
+            ("первая строка новая строка вторая", "первая строка\nвторая"),
+            (
+                "список двоеточие хлеб точка с запятой молоко",
+                "список: хлеб; молоко",
+            ),
+            ("Готово Точка", "Готово."),
+            ("москва тире столица", "москва — столица"),
+            ("запятые и точки остаются", "запятые и точки остаются"),
+            ("отмена рейса в пять", "отмена рейса в пять"),
+            ("нажми кнопку отмена", "нажми кнопку отмена"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                commands.apply(input).as_deref(),
+                Some(expected),
+                "input: {input:?}"
+            );
+        }
+        assert_eq!(commands.apply("Отмена."), None);
+        assert_eq!(commands.apply(" отмена "), None);
+    }
+
+    #[test]
+    fn custom_commands_extend_and_override_defaults() {
+        let settings = SpokenCommandSettings {
+            enabled: true,
+            custom: vec![
+                SpokenCommand {
+                    phrase: "смайлик".to_string(),
+                    action: CommandAction::Insert {
+                        text: "🙂".to_string(),
+                    },
+                },
+                SpokenCommand {
+                    phrase: "Точка".to_string(),
+                    action: CommandAction::Insert {
+                        text: "!".to_string(),
+                    },
+                },
+                SpokenCommand {
+                    phrase: "стоп стоп".to_string(),
+                    action: CommandAction::Cancel,
+                },
+            ],
+        };
+        let commands = SpokenCommands::new(&default_commands(), &settings).unwrap();
+        assert_eq!(
+            commands.apply("спасибо смайлик точка").as_deref(),
+            Some("спасибо 🙂!")
+        );
+        assert_eq!(commands.apply("Стоп  стоп!"), None);
+        assert_eq!(
+            commands.apply("всё не так стоп стоп").as_deref(),
+            Some("всё не так стоп стоп")
+        );
+
+        let empty = SpokenCommandSettings {
+            enabled: true,
+            custom: vec![SpokenCommand {
+                phrase: "  ".to_string(),
+                action: CommandAction::Cancel,
+            }],
+        };
+        assert!(SpokenCommands::new(&default_commands(), &empty)
+            .unwrap_err()
+            .starts_with("spoken command 1"));
+    }
+
+    #[test]
+    fn commands_serialize_with_an_action_tag() {
+        let json = serde_json::to_value(SpokenCommand {
+            phrase: "новая строка".to_string(),
+            action: CommandAction::Insert {
+                text: "\n".to_string(),
+            },
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"phrase": "новая строка", "action": "insert", "text": "\n"})
+        );
+        let cancel: SpokenCommand =
+            serde_json::from_str(r#"{"phrase": "отмена", "action": "cancel"}"#).unwrap();
+        assert_eq!(cancel.action, CommandAction::Cancel);
+    }
+}
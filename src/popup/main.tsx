@@ -2,23 +2,55 @@ import React from "react";
 import ReactDOM from "react-dom/client";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
-import { getSettings, hidePopup, openSettings, type AsrEvent } from "../shared/api";
+import {
+  getSettings,
+  getSidecarStatus,
+  hidePopup,
+  openSettings,
+  type AsrEvent,
+  type RecordingMode,
+  type SidecarStatusReport,
+} from "../shared/api";
 import "./popup.css";
 
-type UiState = "idle" | "listening" | "transcribing" | "done" | "error";
+type UiState = "idle" | "listening" | "transcribing" | "done" | "no-speech" | "error";
 const CLOSE_ANIMATION_MS = 180;
+// The meter spans -60..0 dBFS; a level below SILENT_DBFS for SILENT_HINT_MS suggests a muted mic.
+const METER_FLOOR_DBFS = -60;
+const SILENT_DBFS = -70;
+const SILENT_HINT_MS = 1500;
+const LISTENING_HINTS: Record<RecordingMode, string> = {
+  hold: "Listening. Hold hotkey and speak.",
+  toggle: "Listening. Press hotkey again to stop.",
+  hybrid: "Listening. Tap hotkey to stop, or release if holding.",
+};
+
+function meterFraction(value: number): number {
+  const dbfs = 20 * Math.log10(Math.max(value, 1e-6));
+  return Math.min(1, Math.max(0, 1 - dbfs / METER_FLOOR_DBFS));
+}
 
 function PopupApp() {
   const [state, setState] = React.useState<UiState>("idle");
+  const stateRef = React.useRef<UiState>("idle");
   const [text, setText] = React.useState("");
   const [detail, setDetail] = React.useState("Waiting for hotkey...");
   const [isClosing, setIsClosing] = React.useState(false);
+  const [level, setLevel] = React.useState({ rms: 0, peak: 0 });
   const [isVisible, setIsVisible] = React.useState(false);
   const [enterKey, setEnterKey] = React.useState(0);
+  const [engine, setEngine] = React.useState<SidecarStatusReport>({
+    status: "starting",
+    detail: null,
+    model_loading: false,
+  });
   const hideTimer = React.useRef<number | null>(null);
   const closeTimer = React.useRef<number | null>(null);
   const timeoutSec = React.useRef(10);
+  const recordingMode = React.useRef<RecordingMode>("hold");
   const visibleRef = React.useRef(false);
+  const lastSoundAt = React.useRef(0);
+  const mutedHintShown = React.useRef(false);
   const bars = React.useMemo(() => Array.from({ length: 18 }, (_, i) => i), []);
 
   const resetUi = React.useCallback(() => {
@@ -77,21 +109,58 @@ function PopupApp() {
   }, [clearHideTimer, hideWithAnimation]);
 
   React.useEffect(() => {
-    void getSettings().then((settings) => {
-      timeoutSec.current = settings.popup_timeout_sec;
-    });
+    stateRef.current = state;
+  }, [state]);
+
+  React.useEffect(() => {
+    const refreshSettings = () =>
+      void getSettings().then((settings) => {
+        timeoutSec.current = settings.popup_timeout_sec;
+        recordingMode.current = settings.recording_mode;
+      });
+    refreshSettings();
+    void getSidecarStatus().then(setEngine);
 
     const setup = async () => {
       const unlisten = await listen<AsrEvent>("asr_event", (event) => {
         const payload = event.payload;
+
+        if (payload.event === "audio_level") {
+          const rms = payload.rms ?? 0;
+          setLevel({ rms: meterFraction(rms), peak: meterFraction(payload.peak ?? 0) });
+          const now = Date.now();
+          if (20 * Math.log10(Math.max(rms, 1e-6)) > SILENT_DBFS) {
+            lastSoundAt.current = now;
+            if (mutedHintShown.current) {
+              mutedHintShown.current = false;
+              setDetail(LISTENING_HINTS[recordingMode.current]);
+            }
+          } else if (!mutedHintShown.current && now - lastSoundAt.current > SILENT_HINT_MS) {
+            mutedHintShown.current = true;
+            setDetail("No sound from the microphone. Is it muted?");
+          }
+          return;
+        }
+
         showWindow();
+        if (payload.event !== "recording_started" && payload.event !== "partial_transcript") {
+          setLevel({ rms: 0, peak: 0 });
+        }
 
         if (payload.event === "recording_started") {
+          lastSoundAt.current = Date.now();
+          mutedHintShown.current = false;
           clearHideTimer();
           setIsClosing(false);
           setState("listening");
           setText("");
-          setDetail("Listening. Hold hotkey and speak.");
+          setDetail(LISTENING_HINTS[recordingMode.current]);
+          refreshSettings();
+          return;
+        }
+
+        if (payload.event === "input_device_fallback") {
+          setDetail(payload.message ?? "Using the default microphone.");
           return;
         }
 
@@ -101,10 +170,26 @@ function PopupApp() {
           return;
         }
 
-        if (payload.event === "partial_transcript") {
+        if (payload.event === "auto_stopped") {
+          setState("transcribing");
+          setDetail("Silence detected, transcribing...");
+          return;
+        }
+
+        if (payload.event === "transcription_progress") {
+          clearHideTimer();
           setState("transcribing");
+          setDetail(`Transcribing: ${payload.stage ?? "working"} (${Math.round((payload.progress ?? 0) * 100)}%)`);
+          return;
+        }
+
+        if (payload.event === "partial_transcript") {
           setText(payload.text ?? "");
-          setDetail("Transcribing...");
+          // Live partials arrive while still recording; keep the level meter until it stops.
+          if (stateRef.current !== "listening") {
+            setState("transcribing");
+            setDetail("Transcribing...");
+          }
           return;
         }
 
@@ -124,6 +209,14 @@ function PopupApp() {
           return;
         }
 
+        if (payload.event === "no_speech") {
+          setState("no-speech");
+          setText("");
+          setDetail("Didn't catch that. Nothing was heard, so nothing was copied.");
+          scheduleHide();
+          return;
+        }
+
         if (payload.event === "error") {
           setState("error");
           setText("");
@@ -133,7 +226,14 @@ function PopupApp() {
         }
       });
 
-      return unlisten;
+      const unlistenStatus = await listen<SidecarStatusReport>("sidecar_status", (event) => {
+        setEngine(event.payload);
+      });
+
+      return () => {
+        unlisten();
+        unlistenStatus();
+      };
     };
 
     let current: UnlistenFn | null = null;
@@ -176,6 +276,12 @@ function PopupApp() {
           <span className="status-dot" aria-hidden="true" />
           <p className="detail">{detail}</p>
         </div>
+        {(engine.status !== "ready" || engine.model_loading) && (
+          <p className={`engine-status engine-${engine.status}`}>
+            ASR engine: {engine.model_loading ? "loading model..." : engine.status}
+            {engine.detail ? ` (${engine.detail})` : ""}
+          </p>
+        )}
 
         <div className="visualizer" aria-hidden="true">
           <div className="orb-core" />
@@ -188,6 +294,13 @@ function PopupApp() {
           </div>
         </div>
 
+        {state === "listening" && (
+          <div className="level-meter" aria-label="Microphone level">
+            <span className="level-fill" style={{ width: `${Math.round(level.rms * 100)}%` }} />
+            <span className="level-peak" style={{ left: `${Math.round(level.peak * 100)}%` }} />
+          </div>
+        )}
+
         <p className={`text ${text ? "has-text" : "is-empty"}`}>{text || " "}</p>
       </div>
     </main>
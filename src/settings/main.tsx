@@ -1,16 +1,414 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
-import { getSettings, hideSettings, saveSettings, type AppSettings } from "../shared/api";
+import { listen } from "@tauri-apps/api/event";
+import {
+  clearHistory,
+  copyHistoryEntry,
+  deleteHistoryEntry,
+  exportTranscript,
+  getDefaultSpokenCommands,
+  getReplacementRules,
+  getSupportedLanguages,
+  getSettings,
+  getShortcutRegistrations,
+  hideSettings,
+  listHistory,
+  listInputDevices,
+  listModels,
+  previewReplacements,
+  retranscribe,
+  saveReplacementRules,
+  saveSettings,
+  searchHistory,
+  type AppSettings,
+  type ExportFormat,
+  type HistoryEntry,
+  type InputDevice,
+  type ModelInfo,
+  type MatchKind,
+  type OutputMode,
+  type RecordingMode,
+  type ReplacementRule,
+  type ShortcutAction,
+  type ShortcutRegistration,
+  type SpokenCommand,
+  type SpokenCommandSettings,
+  type TerminalPunctuation,
+} from "../shared/api";
 import "./settings.css";
 
+const EXTRA_SHORTCUTS: { action: ShortcutAction; label: string }[] = [
+  { action: "cancel_job", label: "Cancel current job" },
+  { action: "repaste_last", label: "Re-paste last transcript" },
+  { action: "open_settings", label: "Open settings" },
+  { action: "toggle_mode", label: "Switch recording mode" },
+  { action: "restore_clipboard", label: "Restore clipboard" },
+];
+
+function keymapHotkey(settings: AppSettings, action: ShortcutAction): string {
+  return settings.keymap.find((binding) => binding.action === action)?.hotkey ?? "";
+}
+
+function withKeymapHotkey(settings: AppSettings, action: ShortcutAction, hotkey: string): AppSettings {
+  const keymap = settings.keymap.filter((binding) => binding.action !== action);
+  if (hotkey.trim()) {
+    keymap.push({ action, hotkey });
+  }
+  return { ...settings, keymap };
+}
+
+const MATCH_KINDS: { kind: MatchKind; label: string }[] = [
+  { kind: "literal", label: "Exact text" },
+  { kind: "case_insensitive", label: "Any case" },
+  { kind: "whole_word", label: "Whole word" },
+  { kind: "regex", label: "Regex" },
+];
+
+// Newlines are edited as a visible "\n".
+const showInserted = (text: string) => text.replace(/\n/g, "\\n");
+const readInserted = (text: string) => text.replace(/\\n/g, "\n");
+
+function SpokenCommandsEditor({
+  value,
+  onChange,
+}: {
+  value: SpokenCommandSettings;
+  onChange: (value: SpokenCommandSettings) => void;
+}) {
+  const [defaults, setDefaults] = React.useState<SpokenCommand[]>([]);
+
+  React.useEffect(() => {
+    getDefaultSpokenCommands()
+      .then(setDefaults)
+      .catch(() => setDefaults([]));
+  }, []);
+
+  const update = (index: number, command: SpokenCommand) =>
+    onChange({ ...value, custom: value.custom.map((current, i) => (i === index ? command : current)) });
+
+  const describe = (command: SpokenCommand) =>
+    command.action === "cancel" ? "cancel dictation" : showInserted(command.text);
+
+  return (
+    <>
+      <label className="row-check">
+        <input
+          type="checkbox"
+          checked={value.enabled}
+          onChange={(e) => onChange({ ...value, enabled: e.target.checked })}
+        />
+        <span>Spoken commands ("запятая", "новая строка", "отмена" alone cancels)</span>
+      </label>
+
+      {value.enabled && (
+        <>
+          <small className="hint">
+            Built in: {defaults.map((command) => `${command.phrase} → ${describe(command)}`).join("; ")}
+          </small>
+          {value.custom.map((command, index) => (
+            <div className="rule-row" key={index}>
+              <button
+                type="button"
+                className="secondary"
+                aria-label="Remove"
+                onClick={() => onChange({ ...value, custom: value.custom.filter((_, i) => i !== index) })}
+              >
+                ×
+              </button>
+              <input
+                type="text"
+                placeholder="Phrase"
+                value={command.phrase}
+                onChange={(e) => update(index, { ...command, phrase: e.target.value })}
+              />
+              <input
+                type="text"
+                placeholder="Insert (\n = new line)"
+                disabled={command.action === "cancel"}
+                value={command.action === "insert" ? showInserted(command.text) : ""}
+                onChange={(e) =>
+                  update(index, { phrase: command.phrase, action: "insert", text: readInserted(e.target.value) })
+                }
+              />
+              <select
+                value={command.action}
+                onChange={(e) =>
+                  update(
+                    index,
+                    e.target.value === "cancel"
+                      ? { phrase: command.phrase, action: "cancel" }
+                      : { phrase: command.phrase, action: "insert", text: "" },
+                  )
+                }
+              >
+                <option value="insert">Insert text</option>
+                <option value="cancel">Cancel dictation</option>
+              </select>
+            </div>
+          ))}
+          <div className="footer">
+            <button
+              type="button"
+              className="secondary"
+              onClick={() =>
+                onChange({ ...value, custom: [...value.custom, { phrase: "", action: "insert", text: "" }] })
+              }
+            >
+              Add command
+            </button>
+          </div>
+        </>
+      )}
+    </>
+  );
+}
+
+function ReplacementsPanel() {
+  const [rules, setRules] = React.useState<ReplacementRule[]>([]);
+  const [sample, setSample] = React.useState("");
+  const [preview, setPreview] = React.useState("");
+  const [status, setStatus] = React.useState("");
+
+  React.useEffect(() => {
+    getReplacementRules()
+      .then(setRules)
+      .catch((error) => setStatus(String(error)));
+  }, []);
+
+  React.useEffect(() => {
+    if (!sample.trim()) {
+      setPreview("");
+      return;
+    }
+    previewReplacements(rules, sample)
+      .then(setPreview)
+      .catch((error) => setPreview(String(error)));
+  }, [rules, sample]);
+
+  const update = (index: number, patch: Partial<ReplacementRule>) =>
+    setRules(rules.map((rule, i) => (i === index ? { ...rule, ...patch } : rule)));
+
+  const move = (index: number, offset: number) => {
+    const target = index + offset;
+    if (target < 0 || target >= rules.length) {
+      return;
+    }
+    const next = [...rules];
+    [next[index], next[target]] = [next[target], next[index]];
+    setRules(next);
+  };
+
+  const onSave = async () => {
+    try {
+      setRules(await saveReplacementRules(rules));
+      setStatus("Rules saved");
+    } catch (error) {
+      setStatus(`Save failed: ${String(error)}`);
+    }
+  };
+
+  return (
+    <section className="settings-card">
+      <h2>Replacements</h2>
+      <p className="hint">Rules run top to bottom on every transcript; each one sees the result of the rules above it.</p>
+      <ul className="history-list">
+        {rules.map((rule, index) => (
+          <li key={index}>
+            <div className="rule-row">
+              <input
+                type="checkbox"
+                checked={rule.enabled}
+                onChange={(e) => update(index, { enabled: e.target.checked })}
+                aria-label="Enabled"
+              />
+              <input
+                type="text"
+                placeholder="Heard"
+                value={rule.pattern}
+                onChange={(e) => update(index, { pattern: e.target.value })}
+              />
+              <input
+                type="text"
+                placeholder="Write instead"
+                value={rule.replacement}
+                onChange={(e) => update(index, { replacement: e.target.value })}
+              />
+              <select value={rule.kind} onChange={(e) => update(index, { kind: e.target.value as MatchKind })}>
+                {MATCH_KINDS.map(({ kind, label }) => (
+                  <option key={kind} value={kind}>
+                    {label}
+                  </option>
+                ))}
+              </select>
+            </div>
+            <div className="footer">
+              <button type="button" className="secondary" onClick={() => move(index, -1)}>
+                Up
+              </button>
+              <button type="button" className="secondary" onClick={() => move(index, 1)}>
+                Down
+              </button>
+              <button type="button" className="secondary" onClick={() => setRules(rules.filter((_, i) => i !== index))}>
+                Remove
+              </button>
+            </div>
+          </li>
+        ))}
+        {rules.length === 0 && <li className="history-meta">No rules yet</li>}
+      </ul>
+      <label>
+        <span>Try a phrase</span>
+        <input type="text" value={sample} onChange={(e) => setSample(e.target.value)} />
+        {preview && <small className="hint">{preview}</small>}
+      </label>
+      <div className="footer">
+        <button
+          type="button"
+          className="secondary"
+          onClick={() => setRules([...rules, { pattern: "", replacement: "", kind: "whole_word", enabled: true }])}
+        >
+          Add rule
+        </button>
+        <button type="button" onClick={() => void onSave()}>
+          Save rules
+        </button>
+      </div>
+      <p className="status">{status}</p>
+    </section>
+  );
+}
+
+function HistoryPanel() {
+  const [entries, setEntries] = React.useState<HistoryEntry[]>([]);
+  const [query, setQuery] = React.useState("");
+  const [status, setStatus] = React.useState("");
+  const [exportFormat, setExportFormat] = React.useState<ExportFormat>("srt");
+
+  const refresh = React.useCallback(async (search: string) => {
+    try {
+      setEntries(search.trim() ? await searchHistory(search) : await listHistory(100));
+    } catch (error) {
+      setStatus(String(error));
+    }
+  }, []);
+
+  React.useEffect(() => {
+    void refresh(query);
+  }, [query, refresh]);
+
+  const runExport = async (historyId?: number) => {
+    try {
+      const path = await exportTranscript(exportFormat, historyId);
+      setStatus(path ? `Exported to ${path}` : "");
+    } catch (error) {
+      setStatus(String(error));
+    }
+  };
+
+  const run = async (action: () => Promise<void>, done: string) => {
+    try {
+      await action();
+      setStatus(done);
+      await refresh(query);
+    } catch (error) {
+      setStatus(String(error));
+    }
+  };
+
+  return (
+    <section className="settings-card history-card">
+      <h2>History</h2>
+      <input type="text" placeholder="Search transcripts" value={query} onChange={(e) => setQuery(e.target.value)} />
+      <ul className="history-list">
+        {entries.map((entry) => (
+          <li key={entry.id}>
+            <div className="history-meta">
+              {new Date(entry.timestamp_ms).toLocaleString()}
+              {entry.latency_ms !== null && ` · ${entry.latency_ms} ms`}
+              {entry.device && ` · ${entry.device}`}
+              {entry.audio_path && " · audio saved"}
+            </div>
+            <div className="history-text">{entry.text}</div>
+            <div className="footer">
+              <button type="button" className="secondary" onClick={() => void run(() => copyHistoryEntry(entry.id), "Copied")}>
+                Copy
+              </button>
+              <button type="button" className="secondary" onClick={() => void runExport(entry.id)}>
+                Export
+              </button>
+              {entry.audio_path && (
+                <button
+                  type="button"
+                  className="secondary"
+                  onClick={() => void run(() => retranscribe(entry.id), "Transcribing the saved recording again")}
+                >
+                  Retranscribe
+                </button>
+              )}
+              <button type="button" className="secondary" onClick={() => void run(() => deleteHistoryEntry(entry.id), "Deleted")}>
+                Delete
+              </button>
+            </div>
+          </li>
+        ))}
+        {entries.length === 0 && <li className="history-meta">No transcripts yet</li>}
+      </ul>
+      <div className="footer">
+        <select value={exportFormat} onChange={(e) => setExportFormat(e.target.value as ExportFormat)}>
+          <option value="srt">SRT</option>
+          <option value="vtt">VTT</option>
+          <option value="txt">TXT</option>
+          <option value="json">JSON</option>
+        </select>
+        <button type="button" onClick={() => void runExport()}>
+          Export last transcript
+        </button>
+        <button type="button" className="secondary" onClick={() => void run(clearHistory, "History cleared")}>
+          Clear history
+        </button>
+      </div>
+      <p className="status">{status}</p>
+    </section>
+  );
+}
+
+function modelDetails(model: ModelInfo): string {
+  const decoder = model.e2e ? `${model.decoder.toUpperCase()}, punctuation` : model.decoder.toUpperCase();
+  if (!model.downloaded) {
+    return `${decoder}, not downloaded`;
+  }
+  return model.size_bytes === null ? decoder : `${decoder}, ${Math.round(model.size_bytes / 1_000_000)} MB`;
+}
+
 function SettingsApp() {
   const [settings, setSettings] = React.useState<AppSettings | null>(null);
   const [saving, setSaving] = React.useState(false);
   const [status, setStatus] = React.useState("");
+  const [shortcuts, setShortcuts] = React.useState<ShortcutRegistration[]>([]);
+  const [devices, setDevices] = React.useState<InputDevice[]>([]);
+  const [languages, setLanguages] = React.useState<string[]>([]);
+  const [models, setModels] = React.useState<ModelInfo[]>([]);
+
+  const refreshDevices = React.useCallback(() => {
+    listInputDevices()
+      .then((list) => setDevices(list.devices))
+      .catch((error) => setStatus(`Could not list microphones: ${String(error)}`));
+  }, []);
 
   React.useEffect(() => {
     void getSettings().then((value) => setSettings(value));
-  }, []);
+    void getShortcutRegistrations().then((value) => setShortcuts(value));
+    void getSupportedLanguages().then((value) => setLanguages(value));
+    listModels()
+      .then((list) => setModels(list.models))
+      .catch((error) => setStatus(`Could not list models: ${String(error)}`));
+    refreshDevices();
+
+    const unlisten = listen<AppSettings>("settings_updated", (event) => setSettings(event.payload));
+    return () => {
+      void unlisten.then((dispose) => dispose());
+    };
+  }, [refreshDevices]);
 
   if (!settings) {
     return <main className="settings-shell">Loading settings...</main>;
@@ -23,8 +421,10 @@ function SettingsApp() {
 
     try {
       const saved = await saveSettings(settings);
-      setSettings(saved);
-      setStatus("Saved");
+      setSettings(saved.settings);
+      setShortcuts(saved.shortcuts);
+      const failed = saved.shortcuts.filter((shortcut) => shortcut.error);
+      setStatus(failed.length ? `Saved, but ${failed.length} shortcut(s) could not be registered` : "Saved");
     } catch (error) {
       setStatus(`Save failed: ${String(error)}`);
     } finally {
@@ -32,10 +432,16 @@ function SettingsApp() {
     }
   };
 
+  const shortcutError = (action: ShortcutAction) => {
+    const error = shortcuts.find((shortcut) => shortcut.action === action)?.error;
+    return error ? <small className="field-error">{error}</small> : null;
+  };
+
   return (
     <main className="settings-shell">
       <form className="settings-card" onSubmit={onSave}>
         <h1>Sber Whisper Settings</h1>
+        <p className="hint">Drop a WAV, FLAC or OGG file onto this window to transcribe it.</p>
 
         <label>
           <span>Hotkey</span>
@@ -44,6 +450,120 @@ function SettingsApp() {
             value={settings.hotkey}
             onChange={(e) => setSettings({ ...settings, hotkey: e.target.value })}
           />
+          {shortcutError("dictate")}
+        </label>
+
+        {EXTRA_SHORTCUTS.map(({ action, label }) => (
+          <label key={action}>
+            <span>{label}</span>
+            <input
+              type="text"
+              placeholder="Not bound"
+              value={keymapHotkey(settings, action)}
+              onChange={(e) => setSettings(withKeymapHotkey(settings, action, e.target.value))}
+            />
+            {shortcutError(action)}
+          </label>
+        ))}
+
+        <label>
+          <span>Microphone</span>
+          <div className="select-row">
+            <select
+              value={settings.input_device ?? ""}
+              onChange={(e) => setSettings({ ...settings, input_device: e.target.value || null })}
+            >
+              <option value="">System default</option>
+              {devices.map((device) => (
+                <option key={device.id} value={device.name}>
+                  {device.name} ({Math.round(device.sample_rate / 1000)} kHz{device.is_default ? ", default" : ""})
+                </option>
+              ))}
+              {settings.input_device && !devices.some((device) => device.name === settings.input_device) ? (
+                <option value={settings.input_device}>{settings.input_device} (not connected)</option>
+              ) : null}
+            </select>
+            <button type="button" className="secondary" onClick={refreshDevices}>
+              Refresh
+            </button>
+          </div>
+        </label>
+
+        <label>
+          <span>Language</span>
+          <select value={settings.language_mode} onChange={(e) => setSettings({ ...settings, language_mode: e.target.value })}>
+            <option value="auto">Automatic</option>
+            {languages.map((language) => (
+              <option key={language} value={language}>
+                {language}
+              </option>
+            ))}
+            {settings.language_mode !== "auto" && !languages.includes(settings.language_mode) ? (
+              <option value={settings.language_mode}>{settings.language_mode} (not supported)</option>
+            ) : null}
+          </select>
+        </label>
+
+        <label>
+          <span>Model</span>
+          <select value={settings.model} onChange={(e) => setSettings({ ...settings, model: e.target.value })}>
+            {models.map((model) => (
+              <option key={model.name} value={model.name}>
+                {model.name} ({modelDetails(model)})
+              </option>
+            ))}
+            {!models.some((model) => model.name === settings.model) ? (
+              <option value={settings.model}>{settings.model}</option>
+            ) : null}
+          </select>
+        </label>
+
+        <label>
+          <span>Recording mode</span>
+          <select
+            value={settings.recording_mode}
+            onChange={(e) => setSettings({ ...settings, recording_mode: e.target.value as RecordingMode })}
+          >
+            <option value="hold">Hold to talk</option>
+            <option value="toggle">Tap to start, tap to stop</option>
+            <option value="hybrid">Hybrid (tap toggles, hold talks)</option>
+          </select>
+        </label>
+
+        <label>
+          <span>Transcript output</span>
+          <select
+            value={settings.output_mode}
+            onChange={(e) => setSettings({ ...settings, output_mode: e.target.value as OutputMode })}
+          >
+            <option value="clipboard">Copy to clipboard</option>
+            <option value="paste">Paste into focused app</option>
+            <option value="type">Type into focused app</option>
+          </select>
+        </label>
+
+        {settings.output_mode === "paste" && (
+          <label className="row-check">
+            <input
+              type="checkbox"
+              checked={settings.restore_clipboard}
+              onChange={(e) => setSettings({ ...settings, restore_clipboard: e.target.checked })}
+            />
+            <span>Restore previous clipboard after pasting</span>
+          </label>
+        )}
+
+        <label>
+          <span>Restore previous clipboard after (sec, 0 = never)</span>
+          <input
+            type="number"
+            min={0}
+            max={3600}
+            value={settings.clipboard_restore_sec}
+            onChange={(e) =>
+              setSettings({ ...settings, clipboard_restore_sec: Number.parseInt(e.target.value, 10) || 0 })
+            }
+          />
         </label>
 
         <label>
@@ -72,6 +592,54 @@ function SettingsApp() {
           />
         </label>
 
+        <label>
+          <span>History size (transcripts, 0 = off)</span>
+          <input
+            type="number"
+            min={0}
+            max={100000}
+            value={settings.history_limit}
+            onChange={(e) => setSettings({ ...settings, history_limit: Number.parseInt(e.target.value, 10) || 0 })}
+          />
+        </label>
+
+        <label className="row-check">
+          <input
+            type="checkbox"
+            checked={settings.keep_audio}
+            onChange={(e) => setSettings({ ...settings, keep_audio: e.target.checked })}
+          />
+          <span>Keep recordings with their transcripts</span>
+        </label>
+
+        <label>
+          <span>Keep recordings up to (MB, 0 = no limit)</span>
+          <input
+            type="number"
+            min={0}
+            max={1000000}
+            disabled={!settings.keep_audio}
+            value={settings.audio_retention_mb}
+            onChange={(e) =>
+              setSettings({ ...settings, audio_retention_mb: Number.parseInt(e.target.value, 10) || 0 })
+            }
+          />
+        </label>
+
+        <label>
+          <span>Keep recordings for (days, 0 = no limit)</span>
+          <input
+            type="number"
+            min={0}
+            max={3650}
+            disabled={!settings.keep_audio}
+            value={settings.audio_retention_days}
+            onChange={(e) =>
+              setSettings({ ...settings, audio_retention_days: Number.parseInt(e.target.value, 10) || 0 })
+            }
+          />
+        </label>
+
         <label className="row-check">
           <input
             type="checkbox"
@@ -81,6 +649,134 @@ function SettingsApp() {
           <span>Launch at login</span>
         </label>
 
+        <label className="row-check">
+          <input
+            type="checkbox"
+            checked={settings.streaming_partials}
+            onChange={(e) => setSettings({ ...settings, streaming_partials: e.target.checked })}
+          />
+          <span>Show text while speaking (uses more CPU/GPU while recording)</span>
+        </label>
+
+        <label className="row-check">
+          <input
+            type="checkbox"
+            checked={settings.vad.enabled}
+            onChange={(e) => setSettings({ ...settings, vad: { ...settings.vad, enabled: e.target.checked } })}
+          />
+          <span>Stop recording automatically when I stop speaking</span>
+        </label>
+
+        <label>
+          <span>Silence before auto-stop (ms)</span>
+          <input
+            type="number"
+            min={300}
+            max={10000}
+            step={100}
+            disabled={!settings.vad.enabled}
+            value={settings.vad.vad_silence_ms}
+            onChange={(e) =>
+              setSettings({
+                ...settings,
+                vad: { ...settings.vad, vad_silence_ms: Number.parseInt(e.target.value, 10) || 1500 },
+              })
+            }
+          />
+        </label>
+
+        <label>
+          <span>Speech threshold (dBFS, lower = more sensitive)</span>
+          <input
+            type="number"
+            min={-80}
+            max={-10}
+            value={settings.vad.threshold_dbfs}
+            onChange={(e) =>
+              setSettings({
+                ...settings,
+                vad: { ...settings.vad, threshold_dbfs: Number.parseFloat(e.target.value) || -45 },
+              })
+            }
+          />
+        </label>
+
+        <label className="row-check">
+          <input
+            type="checkbox"
+            checked={settings.normalization.normalize_whitespace}
+            onChange={(e) =>
+              setSettings({
+                ...settings,
+                normalization: { ...settings.normalization, normalize_whitespace: e.target.checked },
+              })
+            }
+          />
+          <span>Tidy up spaces in transcripts</span>
+        </label>
+
+        <label className="row-check">
+          <input
+            type="checkbox"
+            checked={settings.normalization.capitalize_sentences}
+            onChange={(e) =>
+              setSettings({
+                ...settings,
+                normalization: { ...settings.normalization, capitalize_sentences: e.target.checked },
+              })
+            }
+          />
+          <span>Capitalize the start of every sentence</span>
+        </label>
+
+        <label className="row-check">
+          <input
+            type="checkbox"
+            checked={settings.normalization.spoken_numbers}
+            onChange={(e) =>
+              setSettings({
+                ...settings,
+                normalization: { ...settings.normalization, spoken_numbers: e.target.checked },
+              })
+            }
+          />
+          <span>Write spoken numbers as digits ("двадцать пять" → 25)</span>
+        </label>
+
+        <label>
+          <span>Transcript ending</span>
+          <select
+            value={settings.normalization.terminal_punctuation}
+            onChange={(e) =>
+              setSettings({
+                ...settings,
+                normalization: {
+                  ...settings.normalization,
+                  terminal_punctuation: e.target.value as TerminalPunctuation,
+                },
+              })
+            }
+          >
+            <option value="keep">Keep as recognized</option>
+            <option value="add_period">Always end with a period</option>
+            <option value="remove_period">Drop the final period</option>
+          </select>
+        </label>
+
+        <SpokenCommandsEditor
+          value={settings.spoken_commands}
+          onChange={(spoken_commands) => setSettings({ ...settings, spoken_commands })}
+        />
+
+        <label className="row-check">
+          <input
+            type="checkbox"
+            checked={settings.preload_model}
+            onChange={(e) => setSettings({ ...settings, preload_model: e.target.checked })}
+          />
+          <span>Preload model at launch and after idle restarts</span>
+        </label>
+
         <div className="footer">
           <button type="submit" disabled={saving}>
             {saving ? "Saving..." : "Save"}
@@ -92,6 +788,8 @@ function SettingsApp() {
 
         <p className="status">{status}</p>
       </form>
+      <ReplacementsPanel />
+      <HistoryPanel />
     </main>
   );
 }
@@ -1,12 +1,108 @@
 import { invoke } from "@tauri-apps/api/core";
 
+export type RecordingMode = "hold" | "toggle" | "hybrid";
+
+export type OutputMode = "clipboard" | "paste" | "type";
+
+export type ShortcutAction =
+  | "dictate"
+  | "cancel_job"
+  | "repaste_last"
+  | "open_settings"
+  | "toggle_mode"
+  | "restore_clipboard";
+
+export interface ShortcutBinding {
+  action: ShortcutAction;
+  hotkey: string;
+}
+
+export interface ShortcutRegistration {
+  action: ShortcutAction;
+  hotkey: string;
+  error: string | null;
+}
+
+export interface VadSettings {
+  enabled: boolean;
+  vad_silence_ms: number;
+  threshold_dbfs: number;
+}
+
+export type TerminalPunctuation = "keep" | "add_period" | "remove_period";
+
+export interface NormalizationSettings {
+  normalize_whitespace: boolean;
+  spoken_numbers: boolean;
+  capitalize_sentences: boolean;
+  terminal_punctuation: TerminalPunctuation;
+}
+
+export type SpokenCommand =
+  | { phrase: string; action: "insert"; text: string }
+  | { phrase: string; action: "cancel" };
+
+export interface SpokenCommandSettings {
+  enabled: boolean;
+  custom: SpokenCommand[];
+}
+
 export interface AppSettings {
   hotkey: string;
   popup_timeout_sec: number;
   model_keepalive_min: number;
   auto_launch: boolean;
-  language_mode: "ru";
+  /** "auto" or a language code from `getSupportedLanguages`. */
+  language_mode: string;
   theme: "siri_aurora";
+  preload_model: boolean;
+  recording_mode: RecordingMode;
+  keymap: ShortcutBinding[];
+  output_mode: OutputMode;
+  restore_clipboard: boolean;
+  clipboard_restore_sec: number;
+  history_limit: number;
+  streaming_partials: boolean;
+  vad: VadSettings;
+  input_device: string | null;
+  keep_audio: boolean;
+  audio_retention_mb: number;
+  audio_retention_days: number;
+  normalization: NormalizationSettings;
+  spoken_commands: SpokenCommandSettings;
+  /** A GigaAM variant name from `listModels`. */
+  model: string;
+}
+
+export interface InputDevice {
+  id: number;
+  name: string;
+  sample_rate: number;
+  channels: number;
+  is_default: boolean;
+}
+
+export interface InputDeviceList {
+  devices: InputDevice[];
+  default_id: number | null;
+}
+
+export interface ModelInfo {
+  name: string;
+  decoder: string;
+  e2e: boolean;
+  downloaded: boolean;
+  size_bytes: number | null;
+}
+
+export interface ModelList {
+  models: ModelInfo[];
+  current: string;
+}
+
+export interface SavedSettings {
+  settings: AppSettings;
+  shortcuts: ShortcutRegistration[];
 }
 
 export type AsrEventKind =
@@ -17,7 +113,15 @@ export type AsrEventKind =
   | "final_transcript"
   | "job_cancelled"
   | "error"
-  | "metrics";
+  | "metrics"
+  | "transcription_progress"
+  | "transcript_segment"
+  | "auto_stopped"
+  | "no_speech"
+  | "input_device_fallback"
+  | "audio_level"
+  | "recording_saved"
+  | "models";
 
 export interface AsrEvent {
   event: AsrEventKind;
@@ -26,13 +130,67 @@ export interface AsrEvent {
   device?: string;
   model?: string;
   latency_ms?: number;
+  protocol_version?: number;
+  sidecar_version?: string;
+  commands?: string[];
+  stage?: string;
+  progress?: number;
+  index?: number;
+  start_ms?: number;
+  end_ms?: number;
+  silence_ms?: number;
+  requested?: string;
+  rms?: number;
+  peak?: number;
+}
+
+export interface TranscriptSegment {
+  index: number;
+  start_ms: number;
+  end_ms: number;
+  text: string;
+}
+
+export type SidecarStatus = "starting" | "ready" | "degraded" | "failed";
+
+export interface SidecarStatusReport {
+  status: SidecarStatus;
+  detail: string | null;
+  model_loading: boolean;
+}
+
+export interface HistoryEntry {
+  id: number;
+  text: string;
+  timestamp_ms: number;
+  latency_ms: number | null;
+  device: string | null;
+  model: string | null;
+  audio_path: string | null;
+}
+
+export type MatchKind = "literal" | "case_insensitive" | "whole_word" | "regex";
+
+export interface ReplacementRule {
+  pattern: string;
+  replacement: string;
+  kind: MatchKind;
+  enabled: boolean;
+}
+
+export type ExportFormat = "srt" | "vtt" | "txt" | "json";
+
+export interface HealthReport {
+  device: string;
+  model: string;
+  round_trip_ms: number;
 }
 
 export function getSettings(): Promise<AppSettings> {
   return invoke("get_settings");
 }
 
-export function saveSettings(settings: AppSettings): Promise<AppSettings> {
+export function saveSettings(settings: AppSettings): Promise<SavedSettings> {
   return invoke("save_settings", { settings });
 }
 
@@ -47,3 +205,80 @@ export function openSettings(): Promise<void> {
 export function hideSettings(): Promise<void> {
   return invoke("hide_settings_window");
 }
+
+export function healthcheck(): Promise<HealthReport> {
+  return invoke("healthcheck");
+}
+
+export function listInputDevices(): Promise<InputDeviceList> {
+  return invoke("list_input_devices");
+}
+
+export function listModels(): Promise<ModelList> {
+  return invoke("list_models");
+}
+
+export function getSidecarStatus(): Promise<SidecarStatusReport> {
+  return invoke("get_sidecar_status");
+}
+
+export function getShortcutRegistrations(): Promise<ShortcutRegistration[]> {
+  return invoke("get_shortcut_registrations");
+}
+
+export function listHistory(limit?: number): Promise<HistoryEntry[]> {
+  return invoke("list_history", { limit });
+}
+
+export function searchHistory(query: string): Promise<HistoryEntry[]> {
+  return invoke("search_history", { query });
+}
+
+export function copyHistoryEntry(id: number): Promise<void> {
+  return invoke("copy_history_entry", { id });
+}
+
+export function deleteHistoryEntry(id: number): Promise<void> {
+  return invoke("delete_history_entry", { id });
+}
+
+export function clearHistory(): Promise<void> {
+  return invoke("clear_history");
+}
+
+export function getReplacementRules(): Promise<ReplacementRule[]> {
+  return invoke("get_replacement_rules");
+}
+
+export function saveReplacementRules(rules: ReplacementRule[]): Promise<ReplacementRule[]> {
+  return invoke("save_replacement_rules", { rules });
+}
+
+export function previewReplacements(rules: ReplacementRule[], text: string): Promise<string> {
+  return invoke("preview_replacements", { rules, text });
+}
+
+export function getSupportedLanguages(): Promise<string[]> {
+  return invoke("get_supported_languages");
+}
+
+export function getDefaultSpokenCommands(): Promise<SpokenCommand[]> {
+  return invoke("get_default_spoken_commands");
+}
+
+export function retranscribe(historyId: number): Promise<void> {
+  return invoke("retranscribe", { historyId });
+}
+
+export function transcribeFile(path: string): Promise<void> {
+  return invoke("transcribe_file", { path });
+}
+
+export function getLastSegments(): Promise<TranscriptSegment[]> {
+  return invoke("get_last_segments");
+}
+
+/** Resolves to the written path, or null if the save dialog was cancelled. */
+export function exportTranscript(format: ExportFormat, historyId?: number): Promise<string | null> {
+  return invoke("export_transcript", { format, historyId });
+}
@@ -3,7 +3,10 @@ from __future__ import annotations
 import importlib.util
 from pathlib import Path
 import sys
+import tempfile
+import threading
 import unittest
+from unittest import mock
 
 
 MODULE_PATH = Path(__file__).resolve().parents[2] / "python" / "asr_service.py"
@@ -30,10 +33,259 @@ class SidecarTests(unittest.TestCase):
         self.assertEqual(asr_service.STATE.config.language_mode, "ru")
         self.assertEqual(asr_service.STATE.config.popup_timeout_sec, 22)
 
+    def test_quiet_speech_is_kept_when_vad_is_off(self) -> None:
+        # Ten blocks at -50 dBFS: below the default speech threshold, above the silence floor.
+        levels = [-50.0] * 10
+        config = asr_service.RuntimeConfig(vad_silence_ms=0, vad_threshold_dbfs=-45.0)
+        threshold = asr_service.speech_threshold_dbfs(config)
+        self.assertEqual(asr_service.speech_bounds(levels, threshold, 32.0), (0, 10))
+
+        config.vad_silence_ms = 1_500
+        threshold = asr_service.speech_threshold_dbfs(config)
+        self.assertIsNone(asr_service.speech_bounds(levels, threshold, 32.0))
+
+    def test_set_config_can_disable_streaming_partials(self) -> None:
+        self.assertFalse(asr_service.RuntimeConfig().streaming_partials)
+        asr_service.handle_command({"command": "set_config", "config": {"streaming_partials": False}})
+        self.assertFalse(asr_service.STATE.config.streaming_partials)
+        asr_service.start_live_decoder(1)
+        self.assertIsNone(asr_service.STATE.live_thread)
+        asr_service.handle_command({"command": "set_config", "config": {"streaming_partials": True}})
+        self.assertTrue(asr_service.STATE.config.streaming_partials)
+        asr_service.handle_command({"command": "set_config", "config": {"streaming_partials": False}})
+
+    def test_init_reports_protocol_and_commands(self) -> None:
+        asr_service.handle_command({"command": "init", "protocol_version": 1, "capabilities": ["init"]})
+        ready = self.events[-1]
+        self.assertEqual(ready["event"], "ready")
+        self.assertEqual(ready["protocol_version"], asr_service.PROTOCOL_VERSION)
+        self.assertIn("stop_and_transcribe", ready["commands"])
+        self.assertEqual(ready["languages"], ["ru"])
+        self.assertEqual(ready["models"], list(asr_service.MODEL_VARIANTS))
+
+    def test_set_config_rejects_unsupported_language(self) -> None:
+        asr_service.handle_command({"command": "set_config", "config": {"language_mode": "auto"}})
+        self.assertEqual(asr_service.STATE.config.language_mode, "auto")
+        asr_service.handle_command({"command": "set_config", "id": 8, "config": {"language_mode": "en"}})
+        self.assertEqual(asr_service.STATE.config.language_mode, "auto")
+        self.assertEqual(self.events[-1]["event"], "error")
+        self.assertEqual(self.events[-1]["id"], 8)
+        self.assertIn("'en'", self.events[-1]["message"])
+        asr_service.handle_command({"command": "set_config", "config": {"language_mode": "ru"}})
+
     def test_unknown_command_emits_error(self) -> None:
         asr_service.handle_command({"command": "unknown"})
         self.assertTrue(any(e["event"] == "error" for e in self.events))
 
+    def test_healthcheck_echoes_request_id(self) -> None:
+        asr_service.handle_command({"command": "healthcheck", "id": 42})
+        self.assertEqual(self.events[-1]["event"], "metrics")
+        self.assertEqual(self.events[-1]["id"], 42)
+
+    def test_preload_reports_loading_and_loaded(self) -> None:
+        class FakeGigaam:
+            @staticmethod
+            def load_model(name: str, **_kwargs: object) -> object:
+                return object()
+
+        old_gigaam, old_model = asr_service.gigaam, asr_service.STATE.model
+        asr_service.gigaam = FakeGigaam
+        asr_service.STATE.model = None
+        try:
+            asr_service.preload_worker(7)
+        finally:
+            asr_service.gigaam, asr_service.STATE.model = old_gigaam, old_model
+
+        self.assertEqual([e["event"] for e in self.events], ["model_loading", "model_loaded"])
+        self.assertEqual(self.events[-1]["id"], 7)
+
+    def test_model_setting_switches_loaded_model(self) -> None:
+        loaded: list[str] = []
+
+        class FakeGigaam:
+            @staticmethod
+            def load_model(name: str, **_kwargs: object) -> object:
+                loaded.append(name)
+                return object()
+
+        old_gigaam, old_model = asr_service.gigaam, asr_service.STATE.model
+        asr_service.gigaam = FakeGigaam
+        asr_service.STATE.model = object()
+        asr_service.STATE.model_name_used = "v3_e2e_rnnt"
+        try:
+            asr_service.handle_command({"command": "set_config", "id": 9, "config": {"model": "v3_tiny"}})
+            self.assertEqual(self.events[-1]["event"], "error")
+            self.assertEqual(self.events[-1]["id"], 9)
+            asr_service.handle_command({"command": "set_config", "config": {"model": "v3_ctc"}})
+            asr_service.preload_worker(9)
+        finally:
+            asr_service.gigaam, asr_service.STATE.model = old_gigaam, old_model
+            asr_service.STATE.config.model = asr_service.MODEL_NAME
+            asr_service.STATE.model_name_used = asr_service.MODEL_NAME
+
+        self.assertEqual(loaded, ["v3_ctc"])
+        self.assertEqual(self.events[-2], {"event": "model_loading", "id": 9, "model": "v3_ctc"})
+        self.assertEqual(self.events[-1]["model"], "v3_ctc")
+
+    def test_model_swap_waits_for_running_decode(self) -> None:
+        roots: list[str] = []
+
+        class FakeGigaam:
+            @staticmethod
+            def load_model(name: str, download_root: str, **_kwargs: object) -> object:
+                roots.append(download_root)
+                return object()
+
+        old_gigaam, old_model = asr_service.gigaam, asr_service.STATE.model
+        asr_service.gigaam = FakeGigaam
+        decoding_model = asr_service.STATE.model = object()
+        asr_service.STATE.model_name_used = "v3_e2e_rnnt"
+        asr_service.STATE.config.model = "v3_ctc"
+        swap = threading.Thread(target=asr_service.load_model_if_needed)
+        try:
+            with asr_service.STATE.decode_lock:
+                swap.start()
+                swap.join(0.2)
+                # The decode in progress still sees the model it started with.
+                self.assertTrue(swap.is_alive())
+                self.assertIs(asr_service.STATE.model, decoding_model)
+            swap.join(5)
+            self.assertIsNot(asr_service.STATE.model, decoding_model)
+            self.assertEqual(asr_service.STATE.model_name_used, "v3_ctc")
+        finally:
+            asr_service.gigaam, asr_service.STATE.model = old_gigaam, old_model
+            asr_service.STATE.config.model = asr_service.MODEL_NAME
+            asr_service.STATE.model_name_used = asr_service.MODEL_NAME
+
+        self.assertEqual(roots, [str(asr_service.checkpoint_path("v3_ctc").parent)])
+
+    def test_list_models_reports_variants_and_downloaded_sizes(self) -> None:
+        old_gigaam, old_cache_dir = asr_service.gigaam, asr_service.GIGAAM_CACHE_DIR
+        with tempfile.TemporaryDirectory() as cache_dir:
+            (Path(cache_dir) / "v3_ctc.ckpt").write_bytes(b"0" * 10)
+            asr_service.gigaam, asr_service.GIGAAM_CACHE_DIR = None, Path(cache_dir)
+            try:
+                asr_service.handle_command({"command": "list_models", "id": 11})
+            finally:
+                asr_service.gigaam, asr_service.GIGAAM_CACHE_DIR = old_gigaam, old_cache_dir
+
+        reply = self.events[-1]
+        self.assertEqual(reply["event"], "models")
+        self.assertEqual(reply["id"], 11)
+        models = {model["name"]: model for model in reply["models"]}
+        self.assertEqual(set(models), set(asr_service.MODEL_VARIANTS))
+        self.assertEqual(models["v3_ctc"]["size_bytes"], 10)
+        self.assertTrue(models["v3_ctc"]["downloaded"])
+        self.assertIsNone(models["v3_rnnt"]["size_bytes"])
+
+    def test_transcribe_file_rejects_unsupported_or_missing_files(self) -> None:
+        asr_service.handle_command({"command": "transcribe_file", "id": 3, "path": "notes.mp3"})
+        asr_service.handle_command({"command": "transcribe_file", "id": 4, "path": "/nonexistent/notes.wav"})
+        asr_service.handle_command({"command": "transcribe_file", "id": 5})
+
+        self.assertEqual([e["event"] for e in self.events], ["error", "error", "error"])
+        self.assertIn("Unsupported audio format", self.events[0]["message"])
+        self.assertIn("not found", self.events[1]["message"])
+        self.assertEqual([e["id"] for e in self.events], [3, 4, 5])
+
+    def test_plan_chunks_overlaps_and_covers_audio(self) -> None:
+        rate = asr_service.SAMPLE_RATE
+        self.assertEqual(asr_service.plan_chunks(5 * rate), [(0, 5 * rate)])
+
+        chunks = asr_service.plan_chunks(50 * rate, chunk_sec=20.0, overlap_sec=1.0)
+        self.assertEqual(chunks, [(0, 20 * rate), (19 * rate, 39 * rate), (38 * rate, 50 * rate)])
+
+    def test_strip_overlap_drops_repeated_words(self) -> None:
+        previous = "мы встретимся завтра утром".split()
+        self.assertEqual(asr_service.strip_overlap(previous, "Утром, в офисе".split()), ["в", "офисе"])
+        self.assertEqual(asr_service.strip_overlap(previous, "в офисе".split()), ["в", "офисе"])
+
+    def test_vad_stops_only_after_speech_and_trailing_silence(self) -> None:
+        vad = asr_service.EnergyVad(threshold_dbfs=-45.0, silence_ms=300)
+        # Leading silence never stops the recording.
+        self.assertFalse(any(vad.update(-70.0, 32.0) for _ in range(50)))
+        for _ in range(10):
+            self.assertFalse(vad.update(-20.0, 32.0))
+        self.assertFalse(vad.update(-70.0, 160.0))
+        self.assertFalse(vad.update(-30.0, 32.0))
+        self.assertFalse(vad.update(-70.0, 160.0))
+        self.assertTrue(vad.update(-70.0, 160.0))
+
+    def test_speech_bounds_trims_silence_and_rejects_taps(self) -> None:
+        quiet, loud = -70.0, -20.0
+        levels = [quiet] * 5 + [loud] * 8 + [quiet, loud] + [quiet] * 6
+        self.assertEqual(asr_service.speech_bounds(levels, -45.0, 32.0), (5, 15))
+        # A click of a couple of blocks is not speech.
+        self.assertIsNone(asr_service.speech_bounds([quiet, loud, loud, quiet], -45.0, 32.0))
+        self.assertIsNone(asr_service.speech_bounds([], -45.0, 32.0))
+
+    def test_input_device_is_picked_by_name(self) -> None:
+        devices = [
+            {"id": 0, "name": "Built-in Microphone", "sample_rate": 48000, "channels": 1, "is_default": True},
+            {"id": 3, "name": "USB Headset", "sample_rate": 16000, "channels": 1, "is_default": False},
+        ]
+        self.assertEqual(asr_service.pick_input_device(devices, "USB Headset"), 3)
+        self.assertIsNone(asr_service.pick_input_device(devices, "Dock Microphone"))
+
+        asr_service.handle_command({"command": "set_config", "config": {"input_device": "USB Headset"}})
+        self.assertEqual(asr_service.STATE.config.input_device, "USB Headset")
+        asr_service.handle_command({"command": "set_config", "config": {"input_device": None}})
+        self.assertEqual(asr_service.STATE.config.input_device, "")
+
+    def test_device_list_is_refreshed_only_when_the_device_is_missing(self) -> None:
+        connected = [{"id": 3, "name": "USB Headset", "sample_rate": 16000, "channels": 1, "is_default": False}]
+        refreshes: list[bool] = []
+        with mock.patch.object(asr_service, "query_input_devices", return_value=(connected, None)), \
+                mock.patch.object(asr_service, "refresh_device_list", side_effect=lambda: refreshes.append(True)):
+            asr_service.STATE.config.input_device = "USB Headset"
+            self.assertEqual(asr_service.resolve_input_device(), (3, None))
+            self.assertEqual(refreshes, [])
+
+            asr_service.STATE.config.input_device = "Dock Microphone"
+            self.assertEqual(asr_service.resolve_input_device(), (None, "it is not connected"))
+            self.assertEqual(refreshes, [True])
+        asr_service.STATE.config.input_device = ""
+
+    def test_level_meter_reports_at_most_once_per_interval(self) -> None:
+        meter = asr_service.LevelMeter(interval_sec=0.05)
+        self.assertEqual(meter.add(0.0, 0.0, 0, 1.0), None)
+        first = meter.add(512 * 0.25, 0.9, 512, 1.0)
+        self.assertEqual(first, (0.5, 0.9))
+        self.assertIsNone(meter.add(512 * 0.01, 0.2, 512, 1.02))
+        rms, peak = meter.add(512 * 0.01, 0.1, 512, 1.06)
+        self.assertAlmostEqual(rms, 0.1)
+        self.assertEqual(peak, 0.2)
+
+    def test_level_reporter_emits_queued_levels_until_stopped(self) -> None:
+        levels = asr_service.queue.SimpleQueue()
+        levels.put((512 * 0.25, 0.9, 512))
+        levels.put((512 * 0.25, 0.8, 512))
+        levels.put(None)
+        asr_service.level_reporter(levels)
+        # The second block falls within the interval and is held back.
+        self.assertEqual(self.events, [{"event": "audio_level", "rms": 0.5, "peak": 0.9}])
+
+    def test_recordings_dir_is_configurable_and_names_are_flac(self) -> None:
+        asr_service.handle_command({"command": "set_config", "config": {"recordings_dir": "/tmp/rec"}})
+        self.assertEqual(asr_service.STATE.config.recordings_dir, "/tmp/rec")
+        asr_service.handle_command({"command": "set_config", "config": {"recordings_dir": None}})
+        self.assertEqual(asr_service.STATE.config.recordings_dir, "")
+
+        path = asr_service.recording_path(Path("/tmp/rec"), 12, 1_700_000_000.25)
+        self.assertEqual(path.suffix, ".flac")
+        self.assertTrue(path.name.endswith("-250-12.flac"))
+
+    def test_stop_after_auto_stop_is_ignored(self) -> None:
+        asr_service.handle_command({"command": "set_config", "config": {"vad_silence_ms": 900}})
+        self.assertEqual(asr_service.STATE.config.vad_silence_ms, 900)
+        asr_service.STATE.auto_stopped = True
+        try:
+            asr_service.handle_command({"command": "stop_and_transcribe", "id": 4})
+            self.assertEqual(self.events, [])
+        finally:
+            asr_service.STATE.auto_stopped = False
+            asr_service.handle_command({"command": "set_config", "config": {"vad_silence_ms": 0}})
+
 
 if __name__ == "__main__":
     unittest.main()